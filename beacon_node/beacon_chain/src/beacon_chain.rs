@@ -105,6 +105,8 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     /// A state-machine that is updated with information from the network and chooses a canonical
     /// head block.
     pub fork_choice: ForkChoice<T>,
+    /// The graffiti included in produced blocks. Defaults to `GRAFFITI`.
+    pub graffiti: [u8; 32],
     /// Logging to CLI, etc.
     log: Logger,
 }
@@ -154,6 +156,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             canonical_head,
             genesis_block_root,
             fork_choice: ForkChoice::new(store.clone(), &genesis_block, genesis_block_root),
+            graffiti: default_graffiti(),
             store,
             log,
         })
@@ -191,6 +194,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             canonical_head: RwLock::new(p.canonical_head),
             state: RwLock::new(p.state),
             genesis_block_root: p.genesis_block_root,
+            graffiti: default_graffiti(),
             store,
             log,
         }))
@@ -1036,9 +1040,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             state.latest_block_header.canonical_root()
         };
 
-        let mut graffiti: [u8; 32] = [0; 32];
-        graffiti.copy_from_slice(GRAFFITI.as_bytes());
-
         let (proposer_slashings, attester_slashings) =
             self.op_pool.get_slashings(&state, &self.spec);
 
@@ -1055,7 +1056,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     deposit_root: Hash256::zero(),
                     block_hash: Hash256::zero(),
                 },
-                graffiti,
+                graffiti: self.graffiti,
                 proposer_slashings: proposer_slashings.into(),
                 attester_slashings: attester_slashings.into(),
                 attestations: self.op_pool.get_attestations(&state, &self.spec).into(),
@@ -1295,6 +1296,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     }
 }
 
+/// Returns `GRAFFITI` as the fixed-width graffiti of a block.
+fn default_graffiti() -> [u8; 32] {
+    let mut graffiti: [u8; 32] = [0; 32];
+    graffiti.copy_from_slice(GRAFFITI.as_bytes());
+    graffiti
+}
+
 impl From<DBError> for Error {
     fn from(e: DBError) -> Error {
         Error::DBError(e)
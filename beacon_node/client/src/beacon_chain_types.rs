@@ -1,6 +1,6 @@
 use crate::bootstrapper::Bootstrapper;
 use crate::error::Result;
-use crate::{config::GenesisState, ClientConfig};
+use crate::{config::GenesisState, ClientConfig, DETERMINISTIC_PUBKEYS_FILENAME};
 use beacon_chain::{
     lmd_ghost::{LmdGhost, ThreadSafeReducedTree},
     slot_clock::SystemTimeSlotClock,
@@ -9,13 +9,15 @@ use beacon_chain::{
 };
 use slog::{crit, info, Logger};
 use slot_clock::SlotClock;
-use std::fs::File;
+use std::fs::{self, File};
 use std::marker::PhantomData;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tree_hash::TreeHash;
 use types::{
     test_utils::TestingBeaconStateBuilder, BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256,
+    Keypair,
 };
 
 /// Provides a new, initialized `BeaconChain`
@@ -64,13 +66,17 @@ where
             crit!(log, "This release does not support mainnet genesis state.");
             return Err("Mainnet is unsupported".into());
         }
-        GenesisState::RecentGenesis { validator_count } => {
-            generate_testnet_genesis_state(*validator_count, recent_genesis_time(), &spec)
-        }
+        GenesisState::RecentGenesis { validator_count } => generate_testnet_genesis_state(
+            *validator_count,
+            recent_genesis_time(),
+            config,
+            &spec,
+            &log,
+        )?,
         GenesisState::Generated {
             validator_count,
             genesis_time,
-        } => generate_testnet_genesis_state(*validator_count, *genesis_time, &spec),
+        } => generate_testnet_genesis_state(*validator_count, *genesis_time, config, &spec, &log)?,
         GenesisState::Yaml { file } => {
             let file = File::open(file).map_err(|e| {
                 format!("Unable to open YAML genesis state file {:?}: {:?}", file, e)
@@ -89,6 +95,14 @@ where
 
             state
         }
+        GenesisState::CheckpointSync { url } => {
+            crit!(
+                log,
+                "This release does not support checkpoint sync.";
+                "checkpoint_sync_url" => url,
+            );
+            return Err("Checkpoint sync is unsupported".into());
+        }
     };
 
     let mut genesis_block = BeaconBlock::empty(&spec);
@@ -141,18 +155,52 @@ where
     }
 }
 
+/// Generates a genesis state with `validator_count` validators.
+///
+/// If `config.deterministic_keys` is set, the validators always use the interop deterministic
+/// keypairs and their public keys are written to `DETERMINISTIC_PUBKEYS_FILENAME` in the datadir.
 fn generate_testnet_genesis_state<E: EthSpec>(
     validator_count: usize,
     genesis_time: u64,
+    config: &ClientConfig,
     spec: &ChainSpec,
-) -> BeaconState<E> {
-    let (mut genesis_state, _keypairs) =
+    log: &Logger,
+) -> Result<BeaconState<E>> {
+    let builder = if config.deterministic_keys {
+        TestingBeaconStateBuilder::from_deterministic_keypairs(validator_count, spec)
+    } else {
         TestingBeaconStateBuilder::from_default_keypairs_file_if_exists(validator_count, spec)
-            .build();
+    };
+    let (mut genesis_state, keypairs) = builder.build();
 
     genesis_state.genesis_time = genesis_time;
 
-    genesis_state
+    if config.deterministic_keys {
+        let data_dir = config
+            .data_dir()
+            .ok_or("Unable to open or create the datadir")?;
+        let path = data_dir.join(DETERMINISTIC_PUBKEYS_FILENAME);
+        write_pubkeys(&path, &keypairs)?;
+
+        info!(
+            log,
+            "Using deterministic validator keys";
+            "validator_count" => validator_count,
+            "pubkeys" => format!("{:?}", path),
+        );
+    }
+
+    Ok(genesis_state)
+}
+
+/// Writes the `0x`-prefixed public key of each of `keypairs` to `path`, one per line.
+fn write_pubkeys(path: &Path, keypairs: &[Keypair]) -> Result<()> {
+    let contents: String = keypairs
+        .iter()
+        .map(|keypair| format!("{}\n", keypair.pk.as_hex_string()))
+        .collect();
+
+    fs::write(path, contents).map_err(|e| format!("Unable to write {:?}: {}", path, e).into())
 }
 
 /// Returns the system time, mod 30 minutes.
@@ -1,3 +1,4 @@
+use eth2_config::{parse_config, Eth2Config};
 use eth2_libp2p::{
     multiaddr::{Multiaddr, Protocol},
     Enr,
@@ -6,6 +7,7 @@ use reqwest::{Error as HttpError, Url};
 use serde::Deserialize;
 use std::borrow::Cow;
 use std::net::Ipv4Addr;
+use std::path::Path;
 use types::{BeaconBlock, BeaconState, Checkpoint, EthSpec, Hash256, Slot};
 use url::Host;
 
@@ -62,6 +64,23 @@ impl Bootstrapper {
         Some(multiaddr)
     }
 
+    /// Build a multiaddr from the IP address and TCP port advertised in the server's `enr`.
+    ///
+    /// Returns `None` if the ENR lacks either, or advertises an unspecified or loopback address
+    /// which would not be reachable from another host. Unlike `best_effort_multiaddr`, this is
+    /// correct for servers behind a NAT which advertise their public address.
+    pub fn enr_multiaddr(enr: &Enr) -> Option<Multiaddr> {
+        let ip = enr
+            .ip()
+            .filter(|ip| !ip.is_unspecified() && !ip.is_loopback())?;
+        let tcp_port = enr.tcp()?;
+
+        let mut multiaddr = Multiaddr::from(ip);
+        multiaddr.push(Protocol::Tcp(tcp_port));
+
+        Some(multiaddr)
+    }
+
     /// Returns the IPv4 address of the server URL, unless it contains a FQDN.
     pub fn server_ipv4_addr(&self) -> Option<Ipv4Addr> {
         match self.url.host()? {
@@ -75,6 +94,12 @@ impl Bootstrapper {
         get_enr(self.url.clone()).map_err(|e| format!("Unable to get ENR: {:?}", e))
     }
 
+    /// Returns the server's `Eth2Config`.
+    pub fn eth2_config(&self) -> Result<Eth2Config, String> {
+        get_server_eth2_config(self.url.clone())
+            .map_err(|e| format!("Unable to get Eth2Config: {:?}", e))
+    }
+
     /// Returns the servers listening libp2p addresses.
     pub fn listen_port(&self) -> Result<u16, String> {
         get_listen_port(self.url.clone()).map_err(|e| format!("Unable to get listen port: {:?}", e))
@@ -94,6 +119,12 @@ impl Bootstrapper {
         Ok((state, block))
     }
 
+    /// Returns the most recent finalized checkpoint, without downloading its state or block.
+    pub fn finalized_checkpoint(&self) -> Result<Checkpoint, String> {
+        get_finalized_checkpoint(self.url.clone())
+            .map_err(|e| format!("Unable to get finalized checkpoint: {:?}", e))
+    }
+
     /// Returns the most recent finalized state and block.
     pub fn finalized<T: EthSpec>(&self) -> Result<(BeaconState<T>, BeaconBlock<T>), String> {
         let slots_per_epoch = get_slots_per_epoch(self.url.clone())
@@ -112,6 +143,36 @@ impl Bootstrapper {
     }
 }
 
+/// Downloads an `Eth2Config` TOML file from `server`.
+///
+/// Unlike the other bootstrapping methods, `server` is the full URL of the file rather than the
+/// base URL of a Lighthouse HTTP API.
+pub fn get_eth2_config(server: &str) -> Result<Eth2Config, String> {
+    let url = Url::parse(server).map_err(|e| format!("Invalid Eth2Config URL: {}", e))?;
+
+    let contents = reqwest::get(url.clone())
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.text())
+        .map_err(|e| format!("Unable to download Eth2Config from {}: {:?}", url, e))?;
+
+    // The format is chosen by the extension of the URL path, as for files.
+    parse_config(&contents, Path::new(url.path()))
+        .map_err(|e| format!("Unable to parse Eth2Config downloaded from {}: {}", url, e))
+}
+
+fn get_server_eth2_config(mut url: Url) -> Result<Eth2Config, Error> {
+    url.path_segments_mut()
+        .map(|mut url| {
+            url.push("spec").push("eth2_config");
+        })
+        .map_err(|_| Error::InvalidUrl)?;
+
+    reqwest::get(url)?
+        .error_for_status()?
+        .json()
+        .map_err(Into::into)
+}
+
 fn get_slots_per_epoch(mut url: Url) -> Result<Slot, Error> {
     url.path_segments_mut()
         .map(|mut url| {
@@ -125,16 +186,23 @@ fn get_slots_per_epoch(mut url: Url) -> Result<Slot, Error> {
         .map_err(Into::into)
 }
 
-fn get_finalized_slot(mut url: Url, slots_per_epoch: u64) -> Result<Slot, Error> {
+fn get_finalized_slot(url: Url, slots_per_epoch: u64) -> Result<Slot, Error> {
+    let checkpoint = get_finalized_checkpoint(url)?;
+
+    Ok(checkpoint.epoch.start_slot(slots_per_epoch))
+}
+
+fn get_finalized_checkpoint(mut url: Url) -> Result<Checkpoint, Error> {
     url.path_segments_mut()
         .map(|mut url| {
             url.push("beacon").push("latest_finalized_checkpoint");
         })
         .map_err(|_| Error::InvalidUrl)?;
 
-    let checkpoint: Checkpoint = reqwest::get(url)?.error_for_status()?.json()?;
-
-    Ok(checkpoint.epoch.start_slot(slots_per_epoch))
+    reqwest::get(url)?
+        .error_for_status()?
+        .json()
+        .map_err(Into::into)
 }
 
 #[derive(Deserialize)]
@@ -1,11 +1,17 @@
 use crate::{Bootstrapper, Eth2Config};
 use clap::ArgMatches;
+use eth2_config::Eth2Network;
 use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
 use slog::{info, o, warn, Drain};
+use slog_async::OverflowStrategy;
 use std::fs::{self, OpenOptions};
-use std::path::PathBuf;
+use std::net::{TcpListener, UdpSocket};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::SystemTime;
+use types::Address;
+use url::Url;
 
 /// The number initial validators when starting the `Minimal`.
 const TESTNET_VALIDATOR_COUNT: usize = 16;
@@ -13,6 +19,34 @@ const TESTNET_VALIDATOR_COUNT: usize = 16;
 /// The number initial validators when starting the `Minimal`.
 const TESTNET_SPEC_CONSTANTS: &str = "minimal";
 
+/// The file in a `--testnet-dir` containing newline-separated boot node ENRs.
+pub const TESTNET_BOOT_ENR_FILENAME: &str = "boot_enr.txt";
+
+/// The file in a `--testnet-dir` containing the YAML-encoded genesis state.
+pub const TESTNET_GENESIS_STATE_FILENAME: &str = "genesis_state.yaml";
+
+/// The number of bytes of graffiti in a block.
+pub const GRAFFITI_BYTES_LEN: usize = 32;
+
+/// The number of bytes in an address.
+pub const ADDRESS_BYTES_LEN: usize = 20;
+
+/// The file in the datadir to which the ports of the running node are written.
+pub const RUNTIME_CONFIG_FILENAME: &str = "runtime.toml";
+
+/// The file in the datadir to which the public keys of `--deterministic-keys` validators are
+/// written, one per line.
+pub const DETERMINISTIC_PUBKEYS_FILENAME: &str = "deterministic_pubkeys.txt";
+
+/// The presets accepted by `Config::apply_profile`.
+pub const PROFILES: &[&str] = &["staker", "infra-bootnode", "dev"];
+
+/// The number of records buffered by an asynchronous log drain if `--log-buffer-size` is not given.
+pub const DEFAULT_LOG_BUFFER_SIZE: usize = 1024;
+
+/// How far into the future `--genesis-time` and `--genesis-delay` may place genesis (one year).
+pub const MAX_GENESIS_DELAY_SECS: u64 = 365 * 24 * 60 * 60;
+
 /// The core configuration of a Lighthouse beacon node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -21,10 +55,89 @@ pub struct Config {
     db_name: String,
     pub log_file: PathBuf,
     pub spec_constants: String,
+    /// The graffiti to include in produced blocks, as text or `0x`-prefixed hex. See
+    /// `bytes_from_text_or_hex`.
+    #[serde(default)]
+    pub graffiti: Option<String>,
+    /// An operator-chosen address to which fees should be paid.
+    #[serde(default)]
+    pub fee_recipient: Option<Address>,
+    /// The most consecutive skip slots the chain will process before pausing, or `None` for no
+    /// limit.
+    #[serde(default)]
+    pub max_skip_slots: Option<u64>,
+    /// If `true`, a generated genesis state always uses the interop deterministic keypairs, even
+    /// if a keypairs file exists.
+    #[serde(default)]
+    pub deterministic_keys: bool,
+    /// The HTTP API of a trusted node from which to start at its finalized checkpoint, as set by
+    /// `set_checkpoint_sync_url`.
+    #[serde(default)]
+    pub checkpoint_sync_url: Option<String>,
+    /// If `true`, a generated genesis state in the future is waited for before the client starts,
+    /// rather than failing to start.
+    #[serde(default)]
+    pub wait_for_genesis: bool,
+    /// A file to which the base64 ENR of the node is written once the network has started.
+    #[serde(default)]
+    pub enr_output: Option<PathBuf>,
+    // Tables must follow all plain values when serialized to TOML.
     pub genesis_state: GenesisState,
     pub network: network::NetworkConfig,
     pub rpc: rpc::RPCConfig,
     pub rest_api: rest_api::ApiConfig,
+    #[serde(default)]
+    pub external_services: ExternalServices,
+}
+
+/// The ports in use by a running node, written to the datadir once its services have started.
+///
+/// Unlike the client config, this reflects CLI flags such as `--zero-ports`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    pub libp2p_port: u16,
+    pub discovery_port: Option<u16>,
+    pub rpc_port: Option<u16>,
+    pub rest_api_port: Option<u16>,
+}
+
+impl RuntimeConfig {
+    /// Writes `self` as TOML to `RUNTIME_CONFIG_FILENAME` in `data_dir`, atomically.
+    ///
+    /// An existing file is edited in place, keeping any comments an operator has added to it.
+    pub fn write_to_dir(&self, data_dir: &Path) -> Result<PathBuf, String> {
+        let path = data_dir.join(RUNTIME_CONFIG_FILENAME);
+
+        eth2_config::update_toml_file(&path, self)?;
+
+        Ok(path)
+    }
+}
+
+/// Configuration for external services which the node may query.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalServices {
+    /// A http(s) URL of an external endpoint. Validated when set via the CLI.
+    pub endpoint: Option<String>,
+}
+
+impl ExternalServices {
+    /// Sets `self.endpoint`, returning an error if `endpoint` is not a valid http(s) URL.
+    pub fn set_endpoint(&mut self, endpoint: &str) -> Result<(), String> {
+        let url = Url::parse(endpoint)
+            .map_err(|e| format!("Invalid external endpoint {}: {}", endpoint, e))?;
+
+        match url.scheme() {
+            "http" | "https" => {
+                self.endpoint = Some(url.into_string());
+                Ok(())
+            }
+            scheme => Err(format!(
+                "Invalid external endpoint {}: unsupported scheme {}",
+                endpoint, scheme
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +161,10 @@ pub enum GenesisState {
     Yaml { file: PathBuf },
     /// Use a HTTP server (running our REST-API) to load genesis and finalized states and blocks.
     HttpBootstrap { server: String },
+    /// Start from the finalized checkpoint of the Lighthouse HTTP API at `url`.
+    ///
+    /// Not yet supported when the beacon chain is initialized.
+    CheckpointSync { url: String },
 }
 
 impl Default for Config {
@@ -60,6 +177,14 @@ impl Default for Config {
             network: NetworkConfig::new(),
             rpc: rpc::RPCConfig::default(),
             rest_api: rest_api::ApiConfig::default(),
+            external_services: ExternalServices::default(),
+            graffiti: None,
+            fee_recipient: None,
+            max_skip_slots: None,
+            deterministic_keys: false,
+            checkpoint_sync_url: None,
+            wait_for_genesis: false,
+            enr_output: None,
             spec_constants: TESTNET_SPEC_CONSTANTS.into(),
             genesis_state: GenesisState::RecentGenesis {
                 validator_count: TESTNET_VALIDATOR_COUNT,
@@ -75,6 +200,12 @@ impl Config {
             .and_then(|path| Some(path.join(&self.db_name)))
     }
 
+    /// Returns the path to which the client may initialize an on-disk database, without creating
+    /// the datadir.
+    pub fn db_path_unchecked(&self) -> Option<PathBuf> {
+        Some(dirs::home_dir()?.join(&self.data_dir).join(&self.db_name))
+    }
+
     /// Returns the core path for the client.
     pub fn data_dir(&self) -> Option<PathBuf> {
         let path = dirs::home_dir()?.join(&self.data_dir);
@@ -83,7 +214,11 @@ impl Config {
     }
 
     // Update the logger to output in JSON to specified file
-    fn update_logger(&mut self, log: &mut slog::Logger) -> Result<(), &'static str> {
+    fn update_logger(
+        &mut self,
+        log_buffer_size: Option<usize>,
+        log: &mut slog::Logger,
+    ) -> Result<(), &'static str> {
         let file = OpenOptions::new()
             .create(true)
             .write(true)
@@ -108,16 +243,43 @@ impl Config {
         }
 
         let drain = Mutex::new(slog_json::Json::default(file)).fuse();
-        let drain = slog_async::Async::new(drain).build().fuse();
-        *log = slog::Logger::root(drain, o!());
+        *log = match log_buffer_size {
+            Some(log_buffer_size) => {
+                let drain = slog_async::Async::new(drain)
+                    .chan_size(log_buffer_size)
+                    .overflow_strategy(OverflowStrategy::DropAndReport)
+                    .build()
+                    .fuse();
+                slog::Logger::root(drain, o!())
+            }
+            None => slog::Logger::root(drain, o!()),
+        };
 
         Ok(())
     }
 
+    /// Chooses `db_type` from `spec_constants`, unless `--db` is specified in `args`.
+    ///
+    /// Testnets are frequently restarted from genesis so they default to an in-memory database,
+    /// whilst mainnet data should persist. The spec may be replaced after `apply_cli_args`, e.g.,
+    /// by `--eth2-config`, so this must be called once `spec_constants` is final.
+    pub fn apply_db_default(&mut self, args: &ArgMatches) {
+        if args.value_of("db").is_none() {
+            self.db_type = if self.spec_constants == "mainnet" {
+                "disk".to_string()
+            } else {
+                "memory".to_string()
+            };
+        }
+    }
+
     /// Apply the following arguments to `self`, replacing values if they are specified in `args`.
     ///
     /// Returns an error if arguments are obviously invalid. May succeed even if some values are
     /// invalid.
+    ///
+    /// Each argument is applied via a public field or method, so a `Config` may equally be built
+    /// without `clap`, starting from `Config::default()`.
     pub fn apply_cli_args(
         &mut self,
         args: &ArgMatches,
@@ -127,37 +289,490 @@ impl Config {
             self.data_dir = PathBuf::from(dir);
         };
 
-        if let Some(default_spec) = args.value_of("default-spec") {
-            match default_spec {
-                "mainnet" => self.spec_constants = Eth2Config::mainnet().spec_constants,
-                "minimal" => self.spec_constants = Eth2Config::minimal().spec_constants,
-                "interop" => self.spec_constants = Eth2Config::interop().spec_constants,
-                _ => {} // not supported
-            }
+        // A profile only sets defaults, so it is applied before any of the flags it covers.
+        if let Some(profile) = args.value_of("profile") {
+            self.apply_profile(profile)?;
+        }
+
+        // Likewise, a network is applied before the flags that override its spec and boot nodes.
+        if let Some(name) = args.value_of("network") {
+            self.apply_network(Eth2Network::from_name(name)?)?;
+        }
+
+        // A custom `Eth2Config` file is handled when the `Eth2Config` is loaded.
+        if let Some(preset) = args
+            .value_of("default-spec")
+            .and_then(Eth2Config::from_spec_constants)
+        {
+            self.spec_constants = preset.spec_constants;
+        }
+
+        // Without `--db` the database depends on the final spec, see `apply_db_default`.
+        if let Some(db_type) = args.value_of("db") {
+            self.db_type = db_type.to_string();
         }
 
-        if let Some(dir) = args.value_of("db") {
-            self.db_type = dir.to_string();
+        let genesis_time = match (
+            args.value_of("genesis-time"),
+            args.value_of("genesis-delay"),
+        ) {
+            (Some(_), Some(_)) => {
+                return Err("Only one of --genesis-time and --genesis-delay may be used".into())
+            }
+            (Some(time_str), None) => {
+                let genesis_time = time_str
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid genesis time: {}", time_str))?;
+                let now = unix_now()?;
+                check_genesis_time(genesis_time, now)?;
+                if genesis_time < now {
+                    warn!(
+                        log,
+                        "Genesis time is in the past";
+                        "genesis_time" => genesis_time,
+                        "seconds_ago" => now - genesis_time,
+                        "info" => "genesis will be treated as having already occurred"
+                    );
+                }
+                Some(genesis_time)
+            }
+            (None, Some(delay_str)) => {
+                let delay = delay_str
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid genesis delay: {}", delay_str))?;
+                let now = unix_now()?;
+                let genesis_time = now.checked_add(delay).ok_or_else(|| {
+                    format!(
+                        "Genesis delay of {} seconds overflows the genesis time",
+                        delay
+                    )
+                })?;
+                check_genesis_time(genesis_time, now)?;
+                Some(genesis_time)
+            }
+            (None, None) => None,
         };
 
+        if let Some(genesis_time) = genesis_time {
+            self.set_genesis_time(genesis_time);
+        }
+
+        if args.is_present("deterministic-keys") {
+            self.deterministic_keys = true;
+        }
+
+        if args.is_present("wait-for-genesis") {
+            self.wait_for_genesis = true;
+        }
+
+        if let Some(path) = args.value_of("enr-output") {
+            self.enr_output = Some(PathBuf::from(path));
+        }
+
         self.network.apply_cli_args(args)?;
+
+        if let Some(dir) = args.value_of("testnet-dir") {
+            self.apply_testnet_dir(&PathBuf::from(dir), log)?;
+        }
+
         self.rpc.apply_cli_args(args)?;
         self.rest_api.apply_cli_args(args)?;
 
+        // A dry run must not touch the filesystem or bind sockets, so `--zero-ports` and
+        // `--logfile` are only reported.
+        let dry_run = args.is_present("dry-run");
+
+        if args.is_present("zero-ports") {
+            if dry_run {
+                info!(log, "Dry run, not assigning unused ports"; "flag" => "--zero-ports");
+            } else {
+                self.assign_unused_ports(log)?;
+            }
+        }
+
+        if let Some(graffiti) = args.value_of("graffiti") {
+            self.set_graffiti(graffiti)?;
+        }
+
+        if let Some(fee_recipient) = args.value_of("fee-recipient") {
+            self.set_fee_recipient(fee_recipient)?;
+            if self.fee_recipient == Some(Address::zero()) {
+                warn!(
+                    log,
+                    "Fee recipient is the zero address, fees would be burned"
+                );
+            }
+        }
+
+        if let Some(max_skip_slots) = args.value_of("max-skip-slots") {
+            self.set_max_skip_slots(max_skip_slots)?;
+        }
+
+        if let Some(endpoint) = args.value_of("external-endpoint") {
+            self.external_services.set_endpoint(endpoint)?;
+        }
+
         if let Some(log_file) = args.value_of("logfile") {
-            self.log_file = PathBuf::from(log_file);
-            self.update_logger(log)?;
+            let log_buffer_size = match args.value_of("log-buffer-size") {
+                _ if args.is_present("log-sync") => None,
+                Some(size) => Some(
+                    size.parse::<usize>()
+                        .map_err(|_| format!("Invalid log buffer size: {}", size))?,
+                ),
+                None => Some(DEFAULT_LOG_BUFFER_SIZE),
+            };
+            if dry_run {
+                info!(log, "Dry run, not opening the log file"; "logfile" => log_file);
+            } else {
+                self.set_log_file(PathBuf::from(log_file), log_buffer_size, log)?;
+            }
         };
 
         // If the `--bootstrap` flag is provided, overwrite the default configuration.
         if let Some(server) = args.value_of("bootstrap") {
-            do_bootstrapping(self, server.to_string(), &log)?;
+            self.bootstrap(server, log)?;
+        }
+
+        if let Some(url) = args.value_of("checkpoint-sync-url") {
+            self.set_checkpoint_sync_url(url, log)?;
+        }
+
+        self.check_port_collisions()?;
+
+        Ok(())
+    }
+
+    /// Applies the named preset from `PROFILES`, a bundle of settings suited to a common kind of
+    /// deployment.
+    pub fn apply_profile(&mut self, profile: &str) -> Result<(), String> {
+        match profile {
+            "staker" => {
+                // A validator client connects over gRPC and monitoring tools over REST.
+                self.rpc.enabled = true;
+                self.rest_api.enabled = true;
+                self.network.max_peers = 25;
+            }
+            "infra-bootnode" => {
+                self.network.bootnode = true;
+                self.network.disable_discovery = false;
+                self.network.max_peers = 100;
+                self.network.subscribe_all_subnets = true;
+            }
+            "dev" => {
+                self.rpc.enabled = true;
+                self.rest_api.enabled = true;
+                self.network.max_peers = 5;
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown profile: {}, expected one of {}",
+                    profile,
+                    PROFILES.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the spec and boot nodes of `network`, replacing any configured boot nodes.
+    ///
+    /// The `Eth2Config` of the network is handled when the `Eth2Config` is loaded.
+    pub fn apply_network(&mut self, network: &Eth2Network) -> Result<(), String> {
+        self.spec_constants = network.spec_constants.to_string();
+        self.network.boot_nodes = network
+            .boot_enrs
+            .iter()
+            .map(|enr| {
+                enr.parse()
+                    .map_err(|_| format!("Invalid ENR for network {}: {}", network.name, enr))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Sets the graffiti of produced blocks to `graffiti`, either UTF-8 text of at most 32 bytes
+    /// or exactly 32 `0x`-prefixed hex bytes.
+    pub fn set_graffiti(&mut self, graffiti: &str) -> Result<(), String> {
+        bytes_from_text_or_hex(graffiti, GRAFFITI_BYTES_LEN)
+            .map_err(|e| format!("Invalid graffiti: {}", e))?;
+        self.graffiti = Some(graffiti.to_string());
+        Ok(())
+    }
+
+    /// Sets the fee recipient from a `0x`-prefixed hex string of exactly 20 bytes.
+    pub fn set_fee_recipient(&mut self, fee_recipient: &str) -> Result<(), String> {
+        if !fee_recipient.starts_with("0x") {
+            return Err(format!(
+                "Fee recipient must be 0x-prefixed hex: {}",
+                fee_recipient
+            ));
+        }
+        let bytes = hex::decode(&fee_recipient[2..])
+            .map_err(|e| format!("Invalid fee recipient {}: {}", fee_recipient, e))?;
+        if bytes.len() != ADDRESS_BYTES_LEN {
+            return Err(format!(
+                "Fee recipient must be exactly {} bytes, got {} bytes",
+                ADDRESS_BYTES_LEN,
+                bytes.len()
+            ));
+        }
+        self.fee_recipient = Some(Address::from_slice(&bytes));
+        Ok(())
+    }
+
+    /// Sets the most consecutive skip slots the chain will process from a positive integer.
+    pub fn set_max_skip_slots(&mut self, max_skip_slots: &str) -> Result<(), String> {
+        match max_skip_slots.parse::<u64>() {
+            Ok(0) => Err("Max skip slots must be positive".to_string()),
+            Ok(max_skip_slots) => {
+                self.max_skip_slots = Some(max_skip_slots);
+                Ok(())
+            }
+            Err(e) => Err(format!("Invalid max skip slots {}: {}", max_skip_slots, e)),
+        }
+    }
+
+    /// Returns the configured graffiti as bytes, if any.
+    pub fn graffiti_bytes(&self) -> Result<Option<[u8; GRAFFITI_BYTES_LEN]>, String> {
+        match &self.graffiti {
+            Some(graffiti) => {
+                let bytes = bytes_from_text_or_hex(graffiti, GRAFFITI_BYTES_LEN)
+                    .map_err(|e| format!("Invalid graffiti: {}", e))?;
+                let mut array = [0; GRAFFITI_BYTES_LEN];
+                array.copy_from_slice(&bytes);
+                Ok(Some(array))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Generates the genesis state at `genesis_time`, keeping the validator count of any
+    /// previously configured generated genesis state.
+    pub fn set_genesis_time(&mut self, genesis_time: u64) {
+        let validator_count = match self.genesis_state {
+            GenesisState::RecentGenesis { validator_count }
+            | GenesisState::Generated {
+                validator_count, ..
+            } => validator_count,
+            _ => TESTNET_VALIDATOR_COUNT,
+        };
+        self.genesis_state = GenesisState::Generated {
+            validator_count,
+            genesis_time,
+        };
+    }
+
+    /// Writes all further output of `log` as JSON to `log_file`, buffering up to
+    /// `log_buffer_size` records. If `log_buffer_size` is `None`, records are written
+    /// synchronously.
+    pub fn set_log_file(
+        &mut self,
+        log_file: PathBuf,
+        log_buffer_size: Option<usize>,
+        log: &mut slog::Logger,
+    ) -> Result<(), &'static str> {
+        self.log_file = log_file;
+        self.update_logger(log_buffer_size, log)
+    }
+
+    /// Reads the genesis state and a boot node from the Lighthouse HTTP API at `server`.
+    pub fn bootstrap(&mut self, server: &str, log: &slog::Logger) -> Result<(), String> {
+        do_bootstrapping(self, server.to_string(), log)
+    }
+
+    /// Starts the chain from the finalized checkpoint of the Lighthouse HTTP API at `url`.
+    ///
+    /// The checkpoint is fetched immediately so that an unreachable or invalid server is reported
+    /// before the node starts.
+    pub fn set_checkpoint_sync_url(&mut self, url: &str, log: &slog::Logger) -> Result<(), String> {
+        let url = parse_checkpoint_sync_url(url)?;
+
+        let checkpoint = Bootstrapper::from_server_string(url.clone())?
+            .finalized_checkpoint()
+            .map_err(|e| format!("Unable to use checkpoint sync server {}: {}", url, e))?;
+
+        info!(
+            log,
+            "Fetched checkpoint sync finalized checkpoint";
+            "server" => &url,
+            "epoch" => checkpoint.epoch,
+            "root" => format!("{:?}", checkpoint.root),
+        );
+
+        self.genesis_state = GenesisState::CheckpointSync { url: url.clone() };
+        self.checkpoint_sync_url = Some(url);
+
+        Ok(())
+    }
+
+    /// Sets the libp2p, discovery, RPC and REST API ports to ports the OS reports as unused.
+    ///
+    /// Each port is found by binding to port 0 and releasing the socket, so there is a small
+    /// chance another process takes the port before the node binds to it.
+    pub fn assign_unused_ports(&mut self, log: &slog::Logger) -> Result<(), String> {
+        self.network.libp2p_port = unused_port(Transport::Tcp)?;
+        self.network.discovery_port = unused_port(Transport::Udp)?;
+        self.rpc.port = unused_port(Transport::Tcp)?;
+        self.rest_api.port = unused_port(Transport::Tcp)?;
+
+        info!(
+            log,
+            "Assigned unused ports";
+            "libp2p_port" => self.network.libp2p_port,
+            "discovery_port" => self.network.discovery_port,
+            "rpc_port" => self.rpc.port,
+            "rest_api_port" => self.rest_api.port,
+        );
+
+        Ok(())
+    }
+
+    /// Returns an error naming any TCP port that is used by more than one enabled service.
+    ///
+    /// The discovery port is UDP and cannot collide with the TCP services.
+    pub fn check_port_collisions(&self) -> Result<(), String> {
+        let mut tcp_ports = vec![("libp2p", self.network.libp2p_port)];
+        if self.rpc.enabled {
+            tcp_ports.push(("rpc", self.rpc.port));
+        }
+        if self.rest_api.enabled {
+            tcp_ports.push(("rest_api", self.rest_api.port));
+        }
+
+        for (i, (name, port)) in tcp_ports.iter().enumerate() {
+            if let Some((other, _)) = tcp_ports[i + 1..].iter().find(|(_, p)| p == port) {
+                return Err(format!(
+                    "The {} and {} services are both configured to use TCP port {}",
+                    name, other, port
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a fixed-width byte field of `len` bytes from either a `0x`-prefixed hex string of
+/// exactly `len` bytes or UTF-8 text of at most `len` bytes, which is zero-padded.
+pub fn bytes_from_text_or_hex(input: &str, len: usize) -> Result<Vec<u8>, String> {
+    if input.starts_with("0x") {
+        let bytes = hex::decode(&input[2..]).map_err(|e| format!("Invalid hex: {}", e))?;
+        if bytes.len() != len {
+            return Err(format!(
+                "Hex must be exactly {} bytes, got {} bytes",
+                len,
+                bytes.len()
+            ));
+        }
+        Ok(bytes)
+    } else {
+        let mut bytes = input.as_bytes().to_vec();
+        if bytes.len() > len {
+            return Err(format!(
+                "Text must be at most {} bytes, got {} bytes",
+                len,
+                bytes.len()
+            ));
+        }
+        bytes.resize(len, 0);
+        Ok(bytes)
+    }
+}
+
+/// Returns the current time in seconds since the UNIX epoch.
+fn unix_now() -> Result<u64, String> {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .map_err(|e| format!("Unable to read system time: {:?}", e))
+}
+
+/// The transport protocol of a port.
+enum Transport {
+    Tcp,
+    Udp,
+}
+
+/// Returns a port which is currently unused, as assigned by the OS.
+fn unused_port(transport: Transport) -> Result<u16, String> {
+    let local_addr = match transport {
+        Transport::Tcp => TcpListener::bind("0.0.0.0:0").and_then(|listener| listener.local_addr()),
+        Transport::Udp => UdpSocket::bind("0.0.0.0:0").and_then(|socket| socket.local_addr()),
+    }
+    .map_err(|e| format!("Unable to find an unused port: {}", e))?;
+
+    Ok(local_addr.port())
+}
+
+impl Config {
+    /// Applies the optional boot nodes and genesis state from a `--testnet-dir` to `self`.
+    ///
+    /// The spec and client config files in the directory are handled when the configs are first
+    /// loaded.
+    pub fn apply_testnet_dir(&mut self, dir: &PathBuf, log: &slog::Logger) -> Result<(), String> {
+        if !dir.is_dir() {
+            return Err(format!("Testnet directory does not exist: {:?}", dir));
+        }
+
+        let boot_enr_path = dir.join(TESTNET_BOOT_ENR_FILENAME);
+        if boot_enr_path.exists() {
+            let contents = fs::read_to_string(&boot_enr_path)
+                .map_err(|e| format!("Unable to read {:?}: {:?}", boot_enr_path, e))?;
+
+            for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                let enr = line
+                    .parse()
+                    .map_err(|_| format!("Invalid ENR in {:?}: {}", boot_enr_path, line))?;
+                self.network.boot_nodes.push(enr);
+            }
+        }
+
+        let genesis_state_path = dir.join(TESTNET_GENESIS_STATE_FILENAME);
+        if genesis_state_path.exists() {
+            self.genesis_state = GenesisState::Yaml {
+                file: genesis_state_path,
+            };
         }
 
+        info!(
+            log,
+            "Loaded testnet directory";
+            "dir" => format!("{:?}", dir),
+            "boot_nodes" => self.network.boot_nodes.len(),
+            "genesis_state" => format!("{:?}", self.genesis_state),
+        );
+
         Ok(())
     }
 }
 
+/// Returns an error if `genesis_time` is more than `MAX_GENESIS_DELAY_SECS` after `now`, since the
+/// node would wait for it (almost) forever.
+fn check_genesis_time(genesis_time: u64, now: u64) -> Result<(), String> {
+    if genesis_time.saturating_sub(now) > MAX_GENESIS_DELAY_SECS {
+        Err(format!(
+            "Genesis time {} is more than {} seconds in the future",
+            genesis_time, MAX_GENESIS_DELAY_SECS
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns `url` normalized, or an error if it is not a valid http(s) URL.
+fn parse_checkpoint_sync_url(url: &str) -> Result<String, String> {
+    let parsed =
+        Url::parse(url).map_err(|e| format!("Invalid checkpoint sync url {}: {}", url, e))?;
+
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed.into_string()),
+        scheme => Err(format!(
+            "Invalid checkpoint sync url {}: unsupported scheme {}",
+            url, scheme
+        )),
+    }
+}
+
 /// Perform the HTTP bootstrapping procedure, reading an ENR and multiaddr from the HTTP server and
 /// adding them to the `config`.
 fn do_bootstrapping(config: &mut Config, server: String, log: &slog::Logger) -> Result<(), String> {
@@ -168,13 +783,25 @@ fn do_bootstrapping(config: &mut Config, server: String, log: &slog::Logger) ->
 
     let bootstrapper = Bootstrapper::from_server_string(server.to_string())?;
 
-    config.network.boot_nodes.push(bootstrapper.enr()?);
+    let enr = bootstrapper.enr()?;
+
+    // Prefer the address the server advertises in its ENR over one guessed from its URL.
+    let server_multiaddr = Bootstrapper::enr_multiaddr(&enr)
+        .map(|multiaddr| (multiaddr, "enr"))
+        .or_else(|| {
+            bootstrapper
+                .best_effort_multiaddr()
+                .map(|multiaddr| (multiaddr, "http_url"))
+        });
 
-    if let Some(server_multiaddr) = bootstrapper.best_effort_multiaddr() {
+    config.network.boot_nodes.push(enr);
+
+    if let Some((server_multiaddr, source)) = server_multiaddr {
         info!(
             log,
             "Estimated bootstrapper libp2p address";
-            "multiaddr" => format!("{:?}", server_multiaddr)
+            "multiaddr" => format!("{:?}", server_multiaddr),
+            "source" => source,
         );
         config.network.libp2p_nodes.push(server_multiaddr);
     } else {
@@ -186,3 +813,156 @@ fn do_bootstrapping(config: &mut Config, server: String, log: &slog::Logger) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{App, Arg};
+
+    fn matches(args: &[&str]) -> ArgMatches<'static> {
+        App::new("test")
+            .arg(
+                Arg::with_name("fee-recipient")
+                    .long("fee-recipient")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("max-skip-slots")
+                    .long("max-skip-slots")
+                    .takes_value(true),
+            )
+            .arg(Arg::with_name("db").long("db").takes_value(true))
+            .arg(Arg::with_name("profile").long("profile").takes_value(true))
+            .arg(Arg::with_name("network").long("network").takes_value(true))
+            .arg(
+                Arg::with_name("genesis-time")
+                    .long("genesis-time")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("genesis-delay")
+                    .long("genesis-delay")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("maxpeers")
+                    .long("maxpeers")
+                    .takes_value(true),
+            )
+            .get_matches_from(std::iter::once("test").chain(args.iter().cloned()))
+    }
+
+    fn log() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn checkpoint_sync_url_validation() {
+        assert_eq!(
+            parse_checkpoint_sync_url("http://localhost:5052"),
+            Ok("http://localhost:5052/".to_string())
+        );
+        assert!(parse_checkpoint_sync_url("localhost:5052").is_err());
+        assert!(parse_checkpoint_sync_url("ftp://localhost:5052").is_err());
+        assert!(parse_checkpoint_sync_url("not a url").is_err());
+    }
+
+    #[test]
+    fn fee_recipient_invalid_length() {
+        let mut config = Config::default();
+        let result = config.apply_cli_args(&matches(&["--fee-recipient", "0x0102"]), &mut log());
+
+        assert_eq!(
+            result,
+            Err("Fee recipient must be exactly 20 bytes, got 2 bytes".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_network() {
+        let mut config = Config::default();
+        let result = config.apply_cli_args(&matches(&["--network", "unknown"]), &mut log());
+        assert_eq!(
+            result,
+            Err("Unknown network: unknown, expected one of local, interop".to_string())
+        );
+    }
+
+    #[test]
+    fn genesis_delay_overflow() {
+        let mut config = Config::default();
+        let result = config.apply_cli_args(
+            &matches(&["--genesis-delay", &u64::max_value().to_string()]),
+            &mut log(),
+        );
+
+        assert_eq!(
+            result,
+            Err(format!(
+                "Genesis delay of {} seconds overflows the genesis time",
+                u64::max_value()
+            ))
+        );
+    }
+
+    #[test]
+    fn genesis_time_too_far_in_future() {
+        let mut config = Config::default();
+        let result = config.apply_cli_args(
+            &matches(&["--genesis-time", &u64::max_value().to_string()]),
+            &mut log(),
+        );
+
+        assert_eq!(
+            result,
+            Err(format!(
+                "Genesis time {} is more than {} seconds in the future",
+                u64::max_value(),
+                MAX_GENESIS_DELAY_SECS
+            ))
+        );
+    }
+
+    #[test]
+    fn max_skip_slots_must_be_positive() {
+        let mut config = Config::default();
+        let result = config.apply_cli_args(&matches(&["--max-skip-slots", "0"]), &mut log());
+
+        assert_eq!(result, Err("Max skip slots must be positive".to_string()));
+        assert!(config.set_max_skip_slots("-1").is_err());
+    }
+
+    #[test]
+    fn flags_override_profile() {
+        let mut config = Config::default();
+        config
+            .apply_cli_args(
+                &matches(&["--profile", "infra-bootnode", "--maxpeers", "50"]),
+                &mut log(),
+            )
+            .expect("should apply a known profile");
+
+        assert!(config.network.bootnode);
+        assert!(config.network.subscribe_all_subnets);
+        assert_eq!(config.network.max_peers, 50);
+    }
+
+    #[test]
+    fn db_default_follows_final_spec() {
+        let mut config = Config::default();
+        config.apply_cli_args(&matches(&[]), &mut log()).unwrap();
+
+        // As when a mainnet spec is loaded with `--eth2-config` after `apply_cli_args`.
+        config.spec_constants = "mainnet".to_string();
+        config.apply_db_default(&matches(&[]));
+        assert_eq!(config.db_type, "disk");
+
+        config.spec_constants = "minimal".to_string();
+        config.apply_db_default(&matches(&[]));
+        assert_eq!(config.db_type, "memory");
+
+        config.spec_constants = "mainnet".to_string();
+        config.apply_db_default(&matches(&["--db", "memory"]));
+        assert_eq!(config.db_type, "memory");
+    }
+}
@@ -11,21 +11,27 @@ use beacon_chain::BeaconChain;
 use exit_future::Signal;
 use futures::{future::Future, Stream};
 use network::Service as NetworkService;
-use slog::{error, info, o};
+use slog::{error, info, o, warn};
 use slot_clock::SlotClock;
 use std::marker::PhantomData;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::runtime::TaskExecutor;
 use tokio::timer::Interval;
 
 pub use beacon_chain::BeaconChainTypes;
 pub use beacon_chain_types::ClientType;
 pub use beacon_chain_types::InitialiseBeaconChain;
-pub use bootstrapper::Bootstrapper;
-pub use config::{Config as ClientConfig, GenesisState};
+pub use bootstrapper::{get_eth2_config, Bootstrapper};
+pub use config::{
+    bytes_from_text_or_hex, Config as ClientConfig, ExternalServices, GenesisState, RuntimeConfig,
+    DEFAULT_LOG_BUFFER_SIZE, DETERMINISTIC_PUBKEYS_FILENAME, PROFILES, RUNTIME_CONFIG_FILENAME,
+};
 pub use eth2_config::Eth2Config;
 
+/// The interval between countdown messages whilst waiting for genesis.
+const GENESIS_COUNTDOWN_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Main beacon node client service. This provides the connection and initialisation of the clients
 /// sub-services in multiple threads.
 pub struct Client<T: BeaconChainTypes> {
@@ -63,12 +69,25 @@ where
         let seconds_per_slot = eth2_config.spec.seconds_per_slot;
 
         // Load a `BeaconChain` from the store, or create a new one if it does not exist.
-        let beacon_chain = Arc::new(T::initialise_beacon_chain(
+        let mut beacon_chain = T::initialise_beacon_chain(
             store,
             &client_config,
             eth2_config.spec.clone(),
             log.clone(),
-        )?);
+        )?;
+        if let Some(graffiti) = client_config.graffiti_bytes()? {
+            beacon_chain.graffiti = graffiti;
+        }
+        let beacon_chain = Arc::new(beacon_chain);
+
+        match client_config.genesis_state {
+            GenesisState::RecentGenesis { .. } | GenesisState::Generated { .. }
+                if client_config.wait_for_genesis =>
+            {
+                wait_for_genesis(beacon_chain.head().beacon_state.genesis_time, &log)
+            }
+            _ => {}
+        }
 
         if beacon_chain.read_slot_clock().is_none() {
             panic!("Cannot start client before genesis!")
@@ -97,6 +116,14 @@ where
         let (network, network_send) =
             NetworkService::new(beacon_chain.clone(), network_config, executor, log.clone())?;
 
+        if let Some(path) = &client_config.enr_output {
+            let enr = network.local_enr().to_base64();
+            match eth2_config::write_atomically(path, enr.as_bytes()) {
+                Ok(()) => info!(log, "Wrote ENR"; "path" => format!("{:?}", path)),
+                Err(e) => warn!(log, "Unable to write ENR"; "error" => e),
+            }
+        }
+
         // spawn the RPC server
         let rpc_exit_signal = if client_config.rpc.enabled {
             Some(rpc::start_server(
@@ -105,7 +132,7 @@ where
                 network_send.clone(),
                 beacon_chain.clone(),
                 &log,
-            ))
+            )?)
         } else {
             None
         };
@@ -117,6 +144,7 @@ where
                 executor,
                 beacon_chain.clone(),
                 network.clone(),
+                Arc::new(eth2_config.clone()),
                 client_config.db_path().expect("unable to read datadir"),
                 &log,
             ) {
@@ -130,6 +158,28 @@ where
             None
         };
 
+        // Record the ports the services are actually using, so that tooling reading the datadir
+        // agrees with the REST API.
+        let runtime_config = RuntimeConfig {
+            libp2p_port: network.listen_port(),
+            discovery_port: network.local_enr().udp(),
+            rpc_port: rpc_exit_signal.as_ref().map(|_| client_config.rpc.port),
+            rest_api_port: api_exit_signal
+                .as_ref()
+                .map(|_| client_config.rest_api.port),
+        };
+        match client_config.data_dir() {
+            Some(data_dir) => match runtime_config.write_to_dir(&data_dir) {
+                Ok(path) => info!(
+                    log,
+                    "Wrote runtime config";
+                    "path" => format!("{:?}", path),
+                ),
+                Err(e) => warn!(log, "Unable to write runtime config"; "error" => e),
+            },
+            None => warn!(log, "Unable to write runtime config"; "error" => "no datadir"),
+        }
+
         let (slot_timer_exit_signal, exit) = exit_future::signal();
         if let Ok(Some(duration_to_next_slot)) = beacon_chain.slot_clock.duration_to_next_slot() {
             // set up the validator work interval - start at next slot and proceed every slot
@@ -176,6 +226,41 @@ impl<T: BeaconChainTypes> Drop for Client<T> {
     }
 }
 
+/// Blocks the current thread until `genesis_time`, logging the time remaining every
+/// `GENESIS_COUNTDOWN_INTERVAL`.
+fn wait_for_genesis(genesis_time: u64, log: &slog::Logger) {
+    let genesis = match UNIX_EPOCH.checked_add(Duration::from_secs(genesis_time)) {
+        Some(genesis) => genesis,
+        None => {
+            warn!(
+                log,
+                "Genesis time out of range, not waiting";
+                "genesis_time" => genesis_time,
+            );
+            return;
+        }
+    };
+    let mut waited = false;
+
+    while let Ok(remaining) = genesis.duration_since(SystemTime::now()) {
+        if remaining == Duration::from_secs(0) {
+            break;
+        }
+        info!(
+            log,
+            "Waiting for genesis";
+            "seconds_remaining" => remaining.as_secs(),
+            "genesis_time" => genesis_time,
+        );
+        std::thread::sleep(std::cmp::min(remaining, GENESIS_COUNTDOWN_INTERVAL));
+        waited = true;
+    }
+
+    if waited {
+        info!(log, "Genesis reached"; "genesis_time" => genesis_time);
+    }
+}
+
 fn do_state_catchup<T: BeaconChainTypes>(chain: &Arc<BeaconChain<T>>, log: &slog::Logger) {
     // Only attempt to `catchup_state` if we can read the slot clock.
     if let Some(current_slot) = chain.read_slot_clock() {
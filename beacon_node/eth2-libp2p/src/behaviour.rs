@@ -1,6 +1,6 @@
 use crate::config::*;
 use crate::discovery::Discovery;
-use crate::rpc::{RPCEvent, RPCMessage, RPC};
+use crate::rpc::{RPCEvent, RPCMessage, RPCProtocol, RPC};
 use crate::{error, NetworkConfig};
 use crate::{Topic, TopicHash};
 use crate::{BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC};
@@ -10,17 +10,42 @@ use libp2p::{
     discv5::Discv5Event,
     gossipsub::{Gossipsub, GossipsubEvent},
     identify::{Identify, IdentifyEvent},
-    ping::{Ping, PingConfig, PingEvent},
+    ping::{Ping, PingConfig, PingEvent, PingSuccess},
     swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess},
     tokio_io::{AsyncRead, AsyncWrite},
     NetworkBehaviour, PeerId,
 };
 use slog::{debug, o, trace};
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU32;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 const MAX_IDENTIFY_ADDRESSES: usize = 20;
 
+/// The score penalty applied to a peer when an RPC request or response fails.
+const RPC_ERROR_PENALTY: i32 = 10;
+/// The score penalty applied to a peer when it fails to respond to a ping.
+const PING_FAILURE_PENALTY: i32 = 20;
+/// Peers whose score drops to or below this value are disconnected and banned.
+pub const BAN_SCORE_THRESHOLD: i32 = -100;
+/// The duration of a ban applied because of a peer's score.
+pub const SCORE_BAN_DURATION: Duration = Duration::from_secs(3600);
+/// The time in seconds over which a peer's score recovers half way back to zero.
+const SCORE_HALF_LIFE_SECS: f64 = 600.0;
+
+/// The protocol version advertised to peers via identify.
+pub const PROTOCOL_VERSION: &str = "lighthouse/libp2p";
+
+/// The stream protocols negotiated by the libp2p behaviours, other than the eth2 RPC.
+const LIBP2P_PROTOCOL_IDS: [&str; 3] = ["/meshsub/1.0.0", "/ipfs/ping/1.0.0", "/ipfs/id/1.0.0"];
+
+/// Returns the ids of all stream protocols this node offers to peers.
+pub fn supported_protocols() -> Vec<String> {
+    let mut protocols = RPCProtocol.protocol_ids();
+    protocols.extend(LIBP2P_PROTOCOL_IDS.iter().map(|id| id.to_string()));
+    protocols
+}
+
 /// Builds the network behaviour that manages the core protocols of eth2.
 /// This core behaviour is managed by `Behaviour` which adds peer management to all core
 /// behaviours.
@@ -42,6 +67,22 @@ pub struct Behaviour<TSubstream: AsyncRead + AsyncWrite> {
     #[behaviour(ignore)]
     /// The events generated by this behaviour to be consumed in the swarm poll.
     events: Vec<BehaviourEvent>,
+    /// Information learned about connected peers.
+    #[behaviour(ignore)]
+    peer_info: HashMap<PeerId, PeerInfo>,
+    /// The scores of penalized peers. Unlike `peer_info` these are kept when a peer disconnects,
+    /// until they have decayed to zero, so a peer cannot reset its score by reconnecting.
+    #[behaviour(ignore)]
+    scores: HashMap<PeerId, DecayingScore>,
+    /// Peers which are currently banned, with their score at the time of the ban.
+    #[behaviour(ignore)]
+    banned_peers: HashMap<PeerId, PeerScore>,
+    /// Peers whose score has fallen below `BAN_SCORE_THRESHOLD`, waiting to be banned.
+    #[behaviour(ignore)]
+    peers_to_ban: Vec<PeerId>,
+    /// The peers that have announced a subscription to each gossipsub topic.
+    #[behaviour(ignore)]
+    topic_peers: HashMap<TopicHash, HashSet<PeerId>>,
     /// Logger for behaviour actions.
     #[behaviour(ignore)]
     log: slog::Logger,
@@ -63,18 +104,26 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
             .with_keep_alive(false);
 
         let identify = Identify::new(
-            "lighthouse/libp2p".into(),
-            version::version(),
+            PROTOCOL_VERSION.into(),
+            net_conf.agent_version(),
             local_key.public(),
         );
 
         Ok(Behaviour {
-            eth2_rpc: RPC::new(log),
-            gossipsub: Gossipsub::new(local_peer_id.clone(), net_conf.gs_config.clone()),
+            eth2_rpc: RPC::new(
+                log,
+                Duration::from_secs(net_conf.idle_connection_timeout_secs),
+            ),
+            gossipsub: Gossipsub::new(local_peer_id.clone(), net_conf.gs_config()),
             discovery: Discovery::new(local_key, net_conf, log)?,
             ping: Ping::new(ping_config),
             identify,
             events: Vec::new(),
+            peer_info: HashMap::new(),
+            scores: HashMap::new(),
+            banned_peers: HashMap::new(),
+            peers_to_ban: Vec::new(),
+            topic_peers: HashMap::new(),
             log: behaviour_log,
         })
     }
@@ -82,6 +131,133 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
     pub fn discovery(&self) -> &Discovery<TSubstream> {
         &self.discovery
     }
+
+    pub fn discovery_mut(&mut self) -> &mut Discovery<TSubstream> {
+        &mut self.discovery
+    }
+
+    /// Returns the information learned about connected peers.
+    ///
+    /// Peers are only present once they have been identified or pinged.
+    pub fn peer_info(&self) -> &HashMap<PeerId, PeerInfo> {
+        &self.peer_info
+    }
+
+    /// Returns the current score of `peer_id`, which is zero unless it has been penalized.
+    fn score(&self, peer_id: &PeerId, now: Instant) -> i32 {
+        self.scores
+            .get(peer_id)
+            .map_or(0, |score| score.value_at(now))
+    }
+
+    /// Returns the score and state of all connected and banned peers.
+    pub fn peer_scores(&self) -> HashMap<PeerId, PeerScore> {
+        let now = Instant::now();
+        let mut scores: HashMap<PeerId, PeerScore> = self
+            .discovery
+            .connected_peer_set()
+            .iter()
+            .map(|peer_id| {
+                let state = if self.peers_to_ban.contains(peer_id) {
+                    PeerState::Disconnecting
+                } else {
+                    PeerState::Healthy
+                };
+                let score = PeerScore {
+                    score: self.score(peer_id, now),
+                    state,
+                    ban_expiry: None,
+                    ban_reason: None,
+                };
+                (peer_id.clone(), score)
+            })
+            .collect();
+        scores.extend(
+            self.banned_peers
+                .iter()
+                .map(|(peer_id, score)| (peer_id.clone(), score.clone())),
+        );
+        scores
+    }
+
+    /// Returns the connected peers that have announced a subscription to each gossipsub topic.
+    ///
+    /// Gossipsub does not expose its mesh, so this is the set of peers eligible for the mesh of
+    /// each topic rather than the mesh itself.
+    pub fn topic_peers(&self) -> HashMap<TopicHash, Vec<PeerId>> {
+        let connected_peers = self.discovery.connected_peer_set();
+        self.topic_peers
+            .iter()
+            .map(|(topic, peers)| {
+                let peers = peers
+                    .iter()
+                    .filter(|peer_id| connected_peers.contains(peer_id))
+                    .cloned()
+                    .collect();
+                (topic.clone(), peers)
+            })
+            .collect()
+    }
+
+    /// Returns the currently banned peers.
+    pub fn banned_peers(&self) -> &HashMap<PeerId, PeerScore> {
+        &self.banned_peers
+    }
+
+    /// Reduces the score of `peer_id` by `penalty`, queueing the peer to be banned if its score
+    /// falls to or below `BAN_SCORE_THRESHOLD`.
+    fn penalize_peer(&mut self, peer_id: &PeerId, penalty: i32) {
+        let now = Instant::now();
+        let score = self
+            .scores
+            .entry(peer_id.clone())
+            .or_insert_with(|| DecayingScore::new(now))
+            .penalize(penalty, now);
+
+        if score <= BAN_SCORE_THRESHOLD && !self.peers_to_ban.contains(peer_id) {
+            debug!(self.log, "Peer score below ban threshold"; "peer_id" => format!("{}", peer_id), "score" => score);
+            self.peers_to_ban.push(peer_id.clone());
+        }
+    }
+
+    /// Returns the peers whose score has fallen below `BAN_SCORE_THRESHOLD` since the last call.
+    pub(crate) fn take_peers_to_ban(&mut self) -> Vec<PeerId> {
+        std::mem::replace(&mut self.peers_to_ban, Vec::new())
+    }
+
+    /// Records that `peer_id` has been banned until `expiry` because of `reason`.
+    pub(crate) fn record_ban(&mut self, peer_id: PeerId, expiry: SystemTime, reason: String) {
+        let score = self.score(&peer_id, Instant::now());
+        self.banned_peers.insert(
+            peer_id,
+            PeerScore {
+                score,
+                state: PeerState::Banned,
+                ban_expiry: Some(expiry),
+                ban_reason: Some(reason),
+            },
+        );
+    }
+
+    /// Removes the ban on `peer_id`, returning `false` if it was not banned.
+    pub(crate) fn remove_ban(&mut self, peer_id: &PeerId) -> bool {
+        self.banned_peers.remove(peer_id).is_some()
+    }
+
+    /// Removes and returns all peers whose ban has expired.
+    pub(crate) fn take_expired_bans(&mut self) -> Vec<PeerId> {
+        let now = SystemTime::now();
+        let expired: Vec<PeerId> = self
+            .banned_peers
+            .iter()
+            .filter(|(_, score)| score.ban_expiry.map_or(true, |expiry| expiry <= now))
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        for peer_id in &expired {
+            self.banned_peers.remove(peer_id);
+        }
+        expired
+    }
 }
 
 // Implement the NetworkBehaviourEventProcess trait so that we can derive NetworkBehaviour for Behaviour
@@ -101,8 +277,14 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<GossipsubE
                     message: msg,
                 });
             }
-            GossipsubEvent::Subscribed { .. } => {}
-            GossipsubEvent::Unsubscribed { .. } => {}
+            GossipsubEvent::Subscribed { peer_id, topic } => {
+                self.topic_peers.entry(topic).or_default().insert(peer_id);
+            }
+            GossipsubEvent::Unsubscribed { peer_id, topic } => {
+                if let Some(peers) = self.topic_peers.get_mut(&topic) {
+                    peers.remove(&peer_id);
+                }
+            }
         }
     }
 }
@@ -116,9 +298,20 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<RPCMessage
                 self.events.push(BehaviourEvent::PeerDialed(peer_id))
             }
             RPCMessage::PeerDisconnected(peer_id) => {
+                self.peer_info.remove(&peer_id);
+                for peers in self.topic_peers.values_mut() {
+                    peers.remove(&peer_id);
+                }
+                self.topic_peers.retain(|_, peers| !peers.is_empty());
+                // Scores that have recovered are forgotten, keeping the map bounded.
+                let now = Instant::now();
+                self.scores.retain(|_, score| score.value_at(now) != 0);
                 self.events.push(BehaviourEvent::PeerDisconnected(peer_id))
             }
             RPCMessage::RPC(peer_id, rpc_event) => {
+                if let RPCEvent::Error(..) = rpc_event {
+                    self.penalize_peer(&peer_id, RPC_ERROR_PENALTY);
+                }
                 self.events.push(BehaviourEvent::RPC(peer_id, rpc_event))
             }
         }
@@ -128,8 +321,14 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<RPCMessage
 impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<PingEvent>
     for Behaviour<TSubstream>
 {
-    fn inject_event(&mut self, _event: PingEvent) {
-        // not interested in ping responses at the moment.
+    fn inject_event(&mut self, event: PingEvent) {
+        match event.result {
+            Ok(PingSuccess::Ping { rtt }) => {
+                self.peer_info.entry(event.peer).or_default().latency = Some(rtt);
+            }
+            Ok(PingSuccess::Pong) => {}
+            Err(_) => self.penalize_peer(&event.peer, PING_FAILURE_PENALTY),
+        }
     }
 }
 
@@ -162,11 +361,14 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<IdentifyEv
                     info.listen_addrs.truncate(MAX_IDENTIFY_ADDRESSES);
                 }
                 debug!(self.log, "Identified Peer"; "Peer" => format!("{}", peer_id),
-                "Protocol Version" => info.protocol_version,
-                "Agent Version" => info.agent_version,
+                "Protocol Version" => &info.protocol_version,
+                "Agent Version" => &info.agent_version,
                 "Listening Addresses" => format!("{:?}", info.listen_addrs),
                 "Protocols" => format!("{:?}", info.protocols)
                 );
+                let peer_info = self.peer_info.entry(peer_id).or_default();
+                peer_info.agent_version = Some(info.agent_version);
+                peer_info.protocols = info.protocols;
             }
             IdentifyEvent::Error { .. } => {}
             IdentifyEvent::SendBack { .. } => {}
@@ -212,6 +414,76 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
     }
 }
 
+/// Information learned about a connected peer.
+#[derive(Debug, Clone, Default)]
+pub struct PeerInfo {
+    /// The agent version the peer advertised via the identify protocol.
+    pub agent_version: Option<String>,
+    /// The protocols the peer advertised via the identify protocol, e.g., the eth2 RPC protocol
+    /// ids it can serve.
+    pub protocols: Vec<String>,
+    /// The round-trip time of the most recent successful ping to the peer.
+    pub latency: Option<Duration>,
+}
+
+/// A peer's reputation, starting at zero and reduced by misbehaviour. Penalties decay
+/// exponentially with a half life of `SCORE_HALF_LIFE_SECS`.
+#[derive(Debug, Clone, Copy)]
+struct DecayingScore {
+    /// The score at `updated`.
+    score: f64,
+    updated: Instant,
+}
+
+impl DecayingScore {
+    fn new(now: Instant) -> Self {
+        DecayingScore {
+            score: 0.0,
+            updated: now,
+        }
+    }
+
+    fn decayed(&self, now: Instant) -> f64 {
+        let elapsed = now.duration_since(self.updated);
+        let elapsed_secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_millis()) / 1000.0;
+        self.score * 0.5_f64.powf(elapsed_secs / SCORE_HALF_LIFE_SECS)
+    }
+
+    /// Returns the score at `now`, rounded to the nearest integer.
+    fn value_at(&self, now: Instant) -> i32 {
+        self.decayed(now).round() as i32
+    }
+
+    /// Reduces the score by `penalty` at `now`, returning the new score.
+    fn penalize(&mut self, penalty: i32, now: Instant) -> i32 {
+        self.score = self.decayed(now) - f64::from(penalty);
+        self.updated = now;
+        self.value_at(now)
+    }
+}
+
+/// The state of a peer, as determined by its score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeerState {
+    /// The peer is connected and in good standing.
+    Healthy,
+    /// The peer's score has fallen below `BAN_SCORE_THRESHOLD` and it is about to be banned.
+    Disconnecting,
+    /// The peer is banned and connections to or from it are refused.
+    Banned,
+}
+
+/// The score and state of a peer.
+#[derive(Debug, Clone)]
+pub struct PeerScore {
+    pub score: i32,
+    pub state: PeerState,
+    /// The time at which the ban is lifted, if the peer is banned.
+    pub ban_expiry: Option<SystemTime>,
+    /// Why the peer was banned, if it is banned.
+    pub ban_reason: Option<String>,
+}
+
 /// The types of events than can be obtained from polling the behaviour.
 pub enum BehaviourEvent {
     RPC(PeerId, RPCEvent),
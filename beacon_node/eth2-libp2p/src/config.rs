@@ -1,11 +1,15 @@
 use clap::ArgMatches;
 use enr::Enr;
 use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder};
+use libp2p::multiaddr::Protocol;
 use libp2p::Multiaddr;
 use serde_derive::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
 
+/// Environment variable holding comma-separated multiaddrs which are added to `libp2p_nodes`.
+pub const LIBP2P_NODES_ENV_VAR: &str = "LIGHTHOUSE_BOOT_NODES";
+
 /// The gossipsub topic names.
 // These constants form a topic name of the form /TOPIC_PREFIX/TOPIC/ENCODING_POSTFIX
 // For example /eth2/beacon_block/ssz
@@ -17,6 +21,41 @@ pub const VOLUNTARY_EXIT_TOPIC: &str = "voluntary_exit";
 pub const PROPOSER_SLASHING_TOPIC: &str = "proposer_slashing";
 pub const ATTESTER_SLASHING_TOPIC: &str = "attester_slashing";
 pub const SHARD_TOPIC_PREFIX: &str = "shard";
+/// The number of attestation subnets, each with a topic of the form
+/// `/eth2/shard{subnet}_beacon_attestation/ssz`.
+pub const SHARD_SUBNET_COUNT: u64 = 64;
+/// The maximum length of `Config::moniker`.
+pub const MAX_MONIKER_LEN: usize = 32;
+/// The longest a peer may be banned for, one year.
+pub const MAX_BAN_DURATION: Duration = Duration::from_secs(365 * 24 * 3600);
+
+/// Returns the topic name of the attestation subnet `subnet_id`.
+pub fn shard_subnet_topic(subnet_id: u64) -> String {
+    format!(
+        "/{}/{}{}_{}/{}",
+        TOPIC_PREFIX,
+        SHARD_TOPIC_PREFIX,
+        subnet_id,
+        BEACON_ATTESTATION_TOPIC,
+        TOPIC_ENCODING_POSTFIX,
+    )
+}
+
+/// Returns the attestation subnet id of `topic`, if it is the topic of an attestation subnet.
+pub fn subnet_id_from_topic(topic: &str) -> Option<u64> {
+    let prefix = format!("/{}/{}", TOPIC_PREFIX, SHARD_TOPIC_PREFIX);
+    let suffix = format!("_{}/{}", BEACON_ATTESTATION_TOPIC, TOPIC_ENCODING_POSTFIX);
+
+    let subnet_id = topic.get(prefix.len()..)?;
+    if !topic.starts_with(&prefix) || !subnet_id.ends_with(&suffix) {
+        return None;
+    }
+
+    subnet_id[..subnet_id.len() - suffix.len()]
+        .parse::<u64>()
+        .ok()
+        .filter(|&subnet_id| subnet_id < SHARD_SUBNET_COUNT)
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
@@ -28,33 +67,93 @@ pub struct Config {
     /// IP address to listen on.
     pub listen_address: std::net::IpAddr,
 
+    /// Further IP addresses on which libp2p listens, at `libp2p_port`. Discovery only listens on
+    /// `discovery_address`.
+    pub extra_listen_addresses: Vec<std::net::IpAddr>,
+
     /// The TCP port that libp2p listens on.
     pub libp2p_port: u16,
 
-    /// The address to broadcast to peers about which address we are listening on.
+    /// The IP address that discovery listens on, which defaults to the first listen address.
+    ///
+    /// Unless `enr_address` is set, this is also the IP address published in our ENR.
     pub discovery_address: std::net::IpAddr,
 
     /// UDP port that discovery listens on.
     pub discovery_port: u16,
 
+    /// Overrides the IP address published in our ENR, e.g., the public IP of a static NAT.
+    pub enr_address: Option<std::net::IpAddr>,
+
+    /// Overrides the TCP port published in our ENR, e.g., a port forwarded to `libp2p_port`.
+    pub enr_tcp_port: Option<u16>,
+
+    /// Overrides the UDP port published in our ENR.
+    ///
+    /// Note: discv5 binds to the UDP port of the local ENR, so this also changes the port
+    /// discovery listens on.
+    pub enr_udp_port: Option<u16>,
+
     /// Target number of connected peers.
     pub max_peers: usize,
 
-    /// Gossipsub configuration parameters.
-    #[serde(skip)]
-    pub gs_config: GossipsubConfig,
+    /// The number of connected peers below which the node does not report itself as healthy.
+    pub minimum_peers: usize,
+
+    /// The number of seconds a peer is banned for when no duration is given. At most
+    /// `MAX_BAN_DURATION`.
+    pub ban_duration_secs: u64,
+
+    /// The number of seconds a dial, including the secio and muxer upgrades, may take before it
+    /// is abandoned.
+    pub connect_timeout_secs: u64,
 
-    /// List of nodes to initially connect to.
+    /// The number of seconds the RPC keeps an idle connection open, i.e., once it has no open
+    /// substreams. Other protocols may keep the connection open for longer.
+    pub idle_connection_timeout_secs: u64,
+
+    /// The maximum number of discovered peers dialed at once. Further peers wait in a queue.
+    pub max_concurrent_dials: usize,
+
+    /// ENRs of nodes used to bootstrap discv5.
     pub boot_nodes: Vec<Enr>,
 
-    /// List of libp2p nodes to initially connect to.
+    /// Multiaddrs of libp2p nodes to dial directly, e.g., nodes without an ENR.
     pub libp2p_nodes: Vec<Multiaddr>,
 
     /// Client version
     pub client_version: String,
 
+    /// An operator-chosen name appended to `client_version` in the agent string sent to peers,
+    /// see `agent_version`.
+    pub moniker: Option<String>,
+
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<String>,
+
+    /// Subscribe to every attestation subnet topic, in addition to the default topics.
+    pub subscribe_all_subnets: bool,
+
+    /// Disables discv5. Peers are only found via `libp2p_nodes` and other nodes will not be able
+    /// to discover this node.
+    pub disable_discovery: bool,
+
+    /// Attempts to forward the libp2p and discovery ports with UPnP at startup, publishing the
+    /// external IP address of the router in our ENR if successful.
+    pub upnp_enabled: bool,
+
+    /// Declares that this node is run as a stable bootnode for others. This does not change the
+    /// behaviour of the node, it is reported so that infrastructure nodes can be told apart.
+    pub bootnode: bool,
+
+    /// On Unix, create the network dir and key file readable only by the current user and warn if
+    /// an existing key file is readable by others.
+    pub restrict_permissions: bool,
+
+    /// Gossipsub mesh parameters, see `gs_config`.
+    ///
+    /// Tables must follow all plain values when serialized to TOML.
+    pub gossip: GossipConfig,
 }
 
 impl Default for Config {
@@ -66,20 +165,30 @@ impl Default for Config {
         Config {
             network_dir,
             listen_address: "127.0.0.1".parse().expect("valid ip address"),
+            extra_listen_addresses: vec![],
             libp2p_port: 9000,
             discovery_address: "127.0.0.1".parse().expect("valid ip address"),
             discovery_port: 9000,
+            enr_address: None,
+            enr_tcp_port: None,
+            enr_udp_port: None,
             max_peers: 10,
-            // Note: The topics by default are sent as plain strings. Hashes are an optional
-            // parameter.
-            gs_config: GossipsubConfigBuilder::new()
-                .max_transmit_size(1_048_576)
-                .heartbeat_interval(Duration::from_secs(20))
-                .build(),
+            minimum_peers: 1,
+            ban_duration_secs: 3600,
+            connect_timeout_secs: 10,
+            idle_connection_timeout_secs: 30,
+            max_concurrent_dials: 5,
             boot_nodes: vec![],
             libp2p_nodes: vec![],
             client_version: version::version(),
+            moniker: None,
             topics: Vec::new(),
+            subscribe_all_subnets: false,
+            disable_discovery: false,
+            upnp_enabled: true,
+            bootnode: false,
+            restrict_permissions: true,
+            gossip: GossipConfig::default(),
         }
     }
 }
@@ -90,6 +199,47 @@ impl Config {
         Config::default()
     }
 
+    /// Returns the agent string sent to peers via identify, e.g., `Lighthouse/v0.1.0/my-node-1`.
+    pub fn agent_version(&self) -> String {
+        match &self.moniker {
+            Some(moniker) => format!("{}/{}", self.client_version, moniker),
+            None => self.client_version.clone(),
+        }
+    }
+
+    /// Sets `self.moniker`, returning an error if it is empty, longer than `MAX_MONIKER_LEN` or
+    /// contains anything other than printable ASCII without spaces or `/`.
+    pub fn set_moniker(&mut self, moniker: &str) -> Result<(), String> {
+        if moniker.is_empty() || moniker.len() > MAX_MONIKER_LEN {
+            return Err(format!(
+                "Invalid moniker {:?}: must be between 1 and {} characters",
+                moniker, MAX_MONIKER_LEN
+            ));
+        }
+        if !moniker.chars().all(|c| c.is_ascii_graphic() && c != '/') {
+            return Err(format!(
+                "Invalid moniker {:?}: must be printable ASCII without spaces or '/'",
+                moniker
+            ));
+        }
+
+        self.moniker = Some(moniker.to_string());
+        Ok(())
+    }
+
+    /// Returns the gossipsub configuration parameters, using the mesh parameters of `gossip`.
+    pub fn gs_config(&self) -> GossipsubConfig {
+        // Note: The topics by default are sent as plain strings. Hashes are an optional
+        // parameter.
+        GossipsubConfigBuilder::new()
+            .max_transmit_size(1_048_576)
+            .mesh_n(self.gossip.mesh_n)
+            .mesh_n_low(self.gossip.mesh_n_low)
+            .mesh_n_high(self.gossip.mesh_n_high)
+            .heartbeat_interval(Duration::from_millis(self.gossip.heartbeat_interval_ms))
+            .build()
+    }
+
     pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), String> {
         // If a `datadir` has been specified, set the network dir to be inside it.
         if let Some(dir) = args.value_of("datadir") {
@@ -101,12 +251,19 @@ impl Config {
             self.network_dir = PathBuf::from(dir);
         };
 
-        if let Some(listen_address_str) = args.value_of("listen-address") {
-            let listen_address = listen_address_str
-                .parse()
-                .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
-            self.listen_address = listen_address;
-            self.discovery_address = listen_address;
+        // The first listen address is also used for discovery, unless `--discovery-address` is
+        // given.
+        let listen_addresses = list_values(args, "listen-address")
+            .map(|address| {
+                address
+                    .parse()
+                    .map_err(|_| format!("Invalid listen address: {:?}", address))
+            })
+            .collect::<Result<Vec<std::net::IpAddr>, _>>()?;
+        if let Some((listen_address, extra_listen_addresses)) = listen_addresses.split_first() {
+            self.listen_address = *listen_address;
+            self.discovery_address = *listen_address;
+            self.extra_listen_addresses = extra_listen_addresses.to_vec();
         }
 
         if let Some(max_peers_str) = args.value_of("maxpeers") {
@@ -115,17 +272,49 @@ impl Config {
                 .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
         }
 
+        if let Some(minimum_peers_str) = args.value_of("minimum-peers") {
+            self.minimum_peers = minimum_peers_str
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid number of minimum peers: {}", minimum_peers_str))?;
+        }
+
+        if let Some(dials_str) = args.value_of("max-concurrent-dials") {
+            self.max_concurrent_dials = match dials_str.parse::<usize>() {
+                Ok(0) | Err(_) => {
+                    return Err(format!("Invalid max concurrent dials: {}", dials_str))
+                }
+                Ok(dials) => dials,
+            };
+        }
+
+        if let Some(ban_duration_str) = args.value_of("ban-duration") {
+            self.ban_duration_secs = match ban_duration_str.parse::<u64>() {
+                Ok(secs) if secs <= MAX_BAN_DURATION.as_secs() => secs,
+                _ => return Err(format!("Invalid ban duration: {}", ban_duration_str)),
+            };
+        }
+
+        if let Some(timeout_str) = args.value_of("connect-timeout") {
+            self.connect_timeout_secs = match timeout_str.parse::<u64>() {
+                Ok(0) | Err(_) => return Err(format!("Invalid connect timeout: {}", timeout_str)),
+                Ok(timeout) => timeout,
+            };
+        }
+
+        if let Some(timeout_str) = args.value_of("idle-connection-timeout") {
+            self.idle_connection_timeout_secs = timeout_str
+                .parse()
+                .map_err(|_| format!("Invalid idle connection timeout: {}", timeout_str))?;
+        }
+
         if let Some(port_str) = args.value_of("port") {
-            let port = port_str
-                .parse::<u16>()
-                .map_err(|_| format!("Invalid port: {}", port_str))?;
+            let port = parse_port(port_str)?;
             self.libp2p_port = port;
             self.discovery_port = port;
         }
 
-        if let Some(boot_enr_str) = args.value_of("boot-nodes") {
-            self.boot_nodes = boot_enr_str
-                .split(',')
+        if args.is_present("boot-nodes") {
+            self.boot_nodes = list_values(args, "boot-nodes")
                 .map(|enr| enr.parse().map_err(|_| format!("Invalid ENR: {}", enr)))
                 .collect::<Result<Vec<Enr>, _>>()?;
         }
@@ -137,14 +326,94 @@ impl Config {
                     multiaddr
                         .parse()
                         .map_err(|_| format!("Invalid Multiaddr: {}", multiaddr))
+                        .and_then(check_dialable)
                 })
                 .collect::<Result<Vec<Multiaddr>, _>>()?;
         }
 
+        // Multiaddrs supplied via the environment are appended to any given on the command line.
+        if let Ok(env_addresses_str) = std::env::var(LIBP2P_NODES_ENV_VAR) {
+            for multiaddr in env_addresses_str
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+            {
+                let multiaddr = multiaddr.trim();
+                let multiaddr = multiaddr.parse().map_err(|_| {
+                    format!(
+                        "Invalid Multiaddr in {}: {}",
+                        LIBP2P_NODES_ENV_VAR, multiaddr
+                    )
+                })?;
+                self.libp2p_nodes.push(check_dialable(multiaddr)?);
+            }
+        }
+
         if let Some(topics_str) = args.value_of("topics") {
             self.topics = topics_str.split(',').map(|s| s.into()).collect();
         }
 
+        if args.is_present("subscribe-all-subnets") {
+            self.subscribe_all_subnets = true;
+        }
+
+        if args.is_present("disable-discovery") {
+            self.disable_discovery = true;
+        }
+
+        if args.is_present("disable-upnp") {
+            self.upnp_enabled = false;
+        }
+
+        if args.is_present("bootnode") {
+            self.bootnode = true;
+        }
+
+        if let Some(moniker) = args.value_of("moniker") {
+            self.set_moniker(moniker)?;
+        }
+
+        if let Some(mesh_n) = args.value_of("gossip-d") {
+            self.gossip.mesh_n = parse_mesh_size("gossip-d", mesh_n)?;
+        }
+
+        if let Some(mesh_n_low) = args.value_of("gossip-d-low") {
+            self.gossip.mesh_n_low = parse_mesh_size("gossip-d-low", mesh_n_low)?;
+        }
+
+        if let Some(mesh_n_high) = args.value_of("gossip-d-high") {
+            self.gossip.mesh_n_high = parse_mesh_size("gossip-d-high", mesh_n_high)?;
+        }
+
+        if let Some(interval_str) = args.value_of("gossip-heartbeat-interval") {
+            self.gossip.heartbeat_interval_ms = match interval_str.parse::<u64>() {
+                Ok(0) | Err(_) => {
+                    return Err(format!(
+                        "Invalid gossip heartbeat interval: {}",
+                        interval_str
+                    ))
+                }
+                Ok(interval) => interval,
+            };
+        }
+
+        // Values from a config file are checked too, not only those from the CLI.
+        self.gossip.validate()?;
+
+        if self.bootnode && self.disable_discovery {
+            return Err("A bootnode cannot have discovery disabled".into());
+        }
+
+        if self.minimum_peers > self.max_peers {
+            return Err(format!(
+                "Minimum peers ({}) cannot exceed max peers ({})",
+                self.minimum_peers, self.max_peers
+            ));
+        }
+
+        if args.value_of("data-dir-permissions") == Some("default") {
+            self.restrict_permissions = false;
+        }
+
         if let Some(discovery_address_str) = args.value_of("discovery-address") {
             self.discovery_address = discovery_address_str
                 .parse()
@@ -152,11 +421,129 @@ impl Config {
         }
 
         if let Some(disc_port_str) = args.value_of("disc-port") {
-            self.discovery_port = disc_port_str
-                .parse::<u16>()
-                .map_err(|_| format!("Invalid discovery port: {}", disc_port_str))?;
+            self.discovery_port = parse_port(disc_port_str)?;
+        }
+
+        if let Some(enr_address_str) = args.value_of("enr-address") {
+            self.enr_address = Some(
+                enr_address_str
+                    .parse()
+                    .map_err(|_| format!("Invalid ENR address: {:?}", enr_address_str))?,
+            );
+        }
+
+        if let Some(enr_tcp_port_str) = args.value_of("enr-tcp-port") {
+            self.enr_tcp_port = Some(parse_port(enr_tcp_port_str)?);
+        }
+
+        if let Some(enr_udp_port_str) = args.value_of("enr-udp-port") {
+            self.enr_udp_port = Some(parse_port(enr_udp_port_str)?);
         }
 
         Ok(())
     }
 }
+
+/// Gossipsub mesh parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GossipConfig {
+    /// The target number of peers in a topic mesh, gossipsub's `D`.
+    pub mesh_n: usize,
+    /// Peers are added to a mesh with fewer than this many peers, gossipsub's `D_low`.
+    pub mesh_n_low: usize,
+    /// Peers are removed from a mesh with more than this many peers, gossipsub's `D_high`.
+    pub mesh_n_high: usize,
+    /// The time between gossipsub heartbeats, in milliseconds.
+    pub heartbeat_interval_ms: u64,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        GossipConfig {
+            mesh_n: 6,
+            mesh_n_low: 4,
+            mesh_n_high: 12,
+            heartbeat_interval_ms: 20_000,
+        }
+    }
+}
+
+impl GossipConfig {
+    /// Checks that `mesh_n_low <= mesh_n <= mesh_n_high` and that the heartbeat interval is
+    /// non-zero.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mesh_n_low > self.mesh_n || self.mesh_n > self.mesh_n_high {
+            return Err(format!(
+                "Gossip mesh parameters must satisfy D_low <= D <= D_high, got D_low {}, D {}, D_high {}",
+                self.mesh_n_low, self.mesh_n, self.mesh_n_high
+            ));
+        }
+        if self.heartbeat_interval_ms == 0 {
+            return Err("The gossip heartbeat interval must be non-zero".into());
+        }
+        Ok(())
+    }
+}
+
+/// Parses the non-zero mesh size given to the flag `name`.
+fn parse_mesh_size(name: &str, size_str: &str) -> Result<usize, String> {
+    match size_str.parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("Invalid --{}: {}", name, size_str)),
+        Ok(size) => Ok(size),
+    }
+}
+
+/// Returns every entry of the flag `name`, which may be given several times and may contain a
+/// comma-separated list each time. Whitespace around entries is trimmed and empty entries are
+/// skipped.
+fn list_values<'a>(args: &'a ArgMatches, name: &str) -> impl Iterator<Item = &'a str> {
+    args.values_of(name)
+        .into_iter()
+        .flatten()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
+
+/// Returns `multiaddr` if it uses TCP, the only transport supported by the libp2p service.
+///
+/// Other transports (e.g. UDP or QUIC) would parse without error but never connect.
+fn check_dialable(multiaddr: Multiaddr) -> Result<Multiaddr, String> {
+    let mut has_tcp = false;
+    for protocol in multiaddr.iter() {
+        match protocol {
+            Protocol::Tcp(_) => has_tcp = true,
+            Protocol::Udp(_) | Protocol::Quic | Protocol::Utp | Protocol::Udt => {
+                return Err(format!(
+                    "Unsupported Multiaddr transport: {}, only TCP is supported",
+                    multiaddr
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    if has_tcp {
+        Ok(multiaddr)
+    } else {
+        Err(format!(
+            "Multiaddr has no TCP port: {}, only TCP is supported",
+            multiaddr
+        ))
+    }
+}
+
+/// Parses a non-zero port number.
+///
+/// Port 0 is rejected since the OS would assign a random port which is never reported back.
+fn parse_port(port_str: &str) -> Result<u16, String> {
+    match port_str.parse::<u16>() {
+        Ok(0) => Err(format!(
+            "Invalid port: {}, the node would listen on an unknown port",
+            port_str
+        )),
+        Ok(port) => Ok(port),
+        Err(_) => Err(format!("Invalid port: {}", port_str)),
+    }
+}
@@ -9,14 +9,16 @@ use libp2p::core::{identity::Keypair, ConnectedPoint, Multiaddr, PeerId};
 use libp2p::discv5::{Discv5, Discv5Event};
 use libp2p::enr::{Enr, EnrBuilder, NodeId};
 use libp2p::multiaddr::Protocol;
+use libp2p::swarm::protocols_handler::DummyProtocolsHandler;
 use libp2p::swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParameters, ProtocolsHandler};
 use slog::{debug, info, warn};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::ErrorKind;
 use std::path::Path;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_timer::Delay;
 
@@ -24,8 +26,24 @@ use tokio_timer::Delay;
 const MAX_TIME_BETWEEN_PEER_SEARCHES: u64 = 60;
 /// Initial delay between peer searches.
 const INITIAL_SEARCH_DELAY: u64 = 5;
+/// The minimum time between peer searches started with `Discovery::request_peer_search`.
+const MIN_REQUESTED_SEARCH_INTERVAL: Duration = Duration::from_secs(10);
 /// Local ENR storage filename.
 const ENR_FILENAME: &str = "enr.dat";
+/// Filename of the cache of recently seen peer ENRs.
+const PEERS_FILENAME: &str = "peers.json";
+/// The maximum number of peer ENRs persisted to `PEERS_FILENAME`.
+const MAX_PERSISTED_PEERS: usize = 100;
+/// Seconds between writing the cache of recently seen peer ENRs to disk.
+const PEER_PERSIST_INTERVAL: u64 = 60;
+/// The number of dial attempts remembered for each peer.
+const MAX_DIAL_HISTORY: usize = 5;
+/// The maximum number of peers for which dial attempts are remembered.
+const MAX_DIAL_HISTORY_PEERS: usize = 256;
+/// The ENR key of the eth2 fork id, which begins with the 4 byte fork digest.
+pub const ETH2_ENR_KEY: &str = "eth2";
+/// The number of bytes in a fork digest.
+pub const FORK_DIGEST_LEN: usize = 4;
 
 /// Lighthouse discovery behaviour. This provides peer management and discovery using the Discv5
 /// libp2p protocol.
@@ -33,6 +51,33 @@ pub struct Discovery<TSubstream> {
     /// The peers currently connected to libp2p streams.
     connected_peers: HashSet<PeerId>,
 
+    /// The subset of `connected_peers` whose connection was initiated by us.
+    outbound_peers: HashSet<PeerId>,
+
+    /// The remote address of each connected peer.
+    peer_addresses: HashMap<PeerId, Multiaddr>,
+
+    /// The most recent dial attempts to each peer, oldest first.
+    dial_history: HashMap<PeerId, VecDeque<DialAttempt>>,
+
+    /// Peers we are dialing, bounded by `max_concurrent_dials`.
+    dialing: HashSet<PeerId>,
+
+    /// Discovered peers waiting to be dialed, oldest first.
+    dial_queue: VecDeque<PeerId>,
+
+    /// The maximum number of dials in flight at once.
+    max_concurrent_dials: usize,
+
+    /// The time at which a peer last connected, if any has.
+    last_peer_connected: Option<Instant>,
+
+    /// The time at which a peer last disconnected, if any has.
+    last_peer_disconnected: Option<Instant>,
+
+    /// The number of connections established since startup, in either direction.
+    total_connections: u64,
+
     /// The target number of connected peers on the libp2p interface.
     max_peers: usize,
 
@@ -42,17 +87,31 @@ pub struct Discovery<TSubstream> {
     /// The delay between peer discovery searches.
     peer_discovery_delay: Delay,
 
+    /// Recently seen peer ENRs, most recent first. Bounded by `MAX_PERSISTED_PEERS`.
+    seen_enrs: VecDeque<Enr>,
+
+    /// The delay between writes of `seen_enrs` to disk.
+    peer_persist_delay: Delay,
+
     /// Tracks the last discovery delay. The delay is doubled each round until the max
     /// time is reached.
     past_discovery_delay: u64,
 
+    /// The time at which a peer search was last started with `request_peer_search`.
+    last_requested_search: Option<Instant>,
+
     /// The TCP port for libp2p. Used to convert an updated IP address to a multiaddr. Note: This
     /// assumes that the external TCP port is the same as the internal TCP port if behind a NAT.
     //TODO: Improve NAT handling limit the above restriction
     tcp_port: u16,
 
-    /// The discovery behaviour used to discover new peers.
-    discovery: Discv5<TSubstream>,
+    /// The discovery behaviour used to discover new peers. `None` if discovery is disabled, in
+    /// which case no UDP socket is bound.
+    discovery: Option<Discv5<TSubstream>>,
+
+    /// The local ENR, as built at startup. Once discv5 is running its copy is used instead, since
+    /// discv5 updates it.
+    local_enr: Enr,
 
     /// Logger for the discovery behaviour.
     log: slog::Logger,
@@ -77,46 +136,123 @@ impl<TSubstream> Discovery<TSubstream> {
         info!(log, "ENR Initialised"; "ENR" => local_enr.to_base64(), "Seq" => local_enr.seq());
         debug!(log, "Discv5 Node ID Initialised"; "node_id" => format!("{}",local_enr.node_id()));
 
-        let mut discovery = Discv5::new(local_enr, local_key.clone(), config.listen_address)
-            .map_err(|e| format!("Discv5 service failed. Error: {:?}", e))?;
+        let seen_enrs = load_peers_from_disc(&config.network_dir, &log);
 
-        // Add bootnodes to routing table
-        for bootnode_enr in config.boot_nodes.clone() {
-            debug!(
+        let discovery = if config.disable_discovery {
+            info!(
                 log,
-                "Adding node to routing table";
-                "Node ID" => format!("{}",
-                bootnode_enr.node_id())
+                "Discovery is disabled, only connecting to libp2p nodes"
             );
-            discovery.add_enr(bootnode_enr);
-        }
+            None
+        } else {
+            let mut discovery = Discv5::new(
+                local_enr.clone(),
+                local_key.clone(),
+                config.discovery_address,
+            )
+            .map_err(|e| format!("Discv5 service failed. Error: {:?}", e))?;
+
+            // Add bootnodes to routing table
+            for bootnode_enr in config.boot_nodes.clone() {
+                debug!(
+                    log,
+                    "Adding node to routing table";
+                    "Node ID" => format!("{}",
+                    bootnode_enr.node_id())
+                );
+                discovery.add_enr(bootnode_enr);
+            }
+
+            // Add peers seen in previous runs to the routing table.
+            for enr in seen_enrs.iter().cloned() {
+                discovery.add_enr(enr);
+            }
+
+            Some(discovery)
+        };
 
         Ok(Self {
             connected_peers: HashSet::new(),
+            outbound_peers: HashSet::new(),
+            peer_addresses: HashMap::new(),
+            dial_history: HashMap::new(),
+            dialing: HashSet::new(),
+            dial_queue: VecDeque::new(),
+            max_concurrent_dials: config.max_concurrent_dials,
+            last_peer_connected: None,
+            last_peer_disconnected: None,
+            total_connections: 0,
             max_peers: config.max_peers,
             peer_discovery_delay: Delay::new(Instant::now()),
+            seen_enrs,
+            peer_persist_delay: Delay::new(
+                Instant::now() + Duration::from_secs(PEER_PERSIST_INTERVAL),
+            ),
             past_discovery_delay: INITIAL_SEARCH_DELAY,
+            last_requested_search: None,
             tcp_port: config.libp2p_port,
             discovery,
+            local_enr,
             log,
             enr_dir,
         })
     }
 
     pub fn local_enr(&self) -> &Enr {
-        self.discovery.local_enr()
+        match &self.discovery {
+            Some(discovery) => discovery.local_enr(),
+            None => &self.local_enr,
+        }
+    }
+
+    /// Returns `true` if discv5 is being used to find peers.
+    pub fn is_enabled(&self) -> bool {
+        self.discovery.is_some()
     }
 
     /// Manually search for peers. This restarts the discovery round, sparking multiple rapid
     /// queries.
+    ///
+    /// Has no effect if discovery is disabled.
     pub fn discover_peers(&mut self) {
+        if !self.is_enabled() {
+            return;
+        }
         self.past_discovery_delay = INITIAL_SEARCH_DELAY;
         self.find_peers();
     }
 
+    /// Starts a peer search on behalf of an operator, e.g., via the REST API, as with
+    /// `discover_peers`.
+    ///
+    /// Returns the time until another search may be requested if one was requested less than
+    /// `MIN_REQUESTED_SEARCH_INTERVAL` ago. Has no effect if discovery is disabled.
+    pub fn request_peer_search(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        if let Some(last) = self.last_requested_search {
+            let elapsed = now.duration_since(last);
+            if elapsed < MIN_REQUESTED_SEARCH_INTERVAL {
+                return Err(MIN_REQUESTED_SEARCH_INTERVAL - elapsed);
+            }
+        }
+
+        self.last_requested_search = Some(now);
+        self.discover_peers();
+        Ok(())
+    }
+
+    /// The number of recently seen peer ENRs.
+    pub fn seen_enr_count(&self) -> usize {
+        self.seen_enrs.len()
+    }
+
     /// Add an Enr to the routing table of the discovery mechanism.
+    ///
+    /// Has no effect if discovery is disabled.
     pub fn add_enr(&mut self, enr: Enr) {
-        self.discovery.add_enr(enr);
+        if let Some(discovery) = self.discovery.as_mut() {
+            discovery.add_enr(enr);
+        }
     }
 
     /// The current number of connected libp2p peers.
@@ -129,12 +265,128 @@ impl<TSubstream> Discovery<TSubstream> {
         &self.connected_peers
     }
 
+    /// The current number of connected libp2p peers that we dialed.
+    pub fn outbound_peers(&self) -> usize {
+        self.outbound_peers.len()
+    }
+
+    /// The remote address of each connected peer.
+    pub fn peer_addresses(&self) -> &HashMap<PeerId, Multiaddr> {
+        &self.peer_addresses
+    }
+
+    /// The target number of connected peers.
+    pub fn max_peers(&self) -> usize {
+        self.max_peers
+    }
+
+    /// The time at which a peer last connected, if any has.
+    pub fn last_peer_connected(&self) -> Option<Instant> {
+        self.last_peer_connected
+    }
+
+    /// The time at which a peer last disconnected, if any has.
+    pub fn last_peer_disconnected(&self) -> Option<Instant> {
+        self.last_peer_disconnected
+    }
+
+    /// The number of connections established since startup, in either direction.
+    pub fn total_connections(&self) -> u64 {
+        self.total_connections
+    }
+
+    /// The number of dials in flight.
+    pub fn dials_in_flight(&self) -> usize {
+        self.dialing.len()
+    }
+
+    /// The number of discovered peers waiting to be dialed.
+    pub fn queued_dials(&self) -> usize {
+        self.dial_queue.len()
+    }
+
+    /// The most recent dial attempts to each peer, oldest first.
+    pub fn dial_history(&self) -> &HashMap<PeerId, VecDeque<DialAttempt>> {
+        &self.dial_history
+    }
+
+    /// Returns the recently discovered ENRs whose `eth2` field begins with `fork_digest`, most
+    /// recently seen first.
+    pub fn enrs_with_fork_digest(&self, fork_digest: [u8; FORK_DIGEST_LEN]) -> Vec<Enr> {
+        self.seen_enrs
+            .iter()
+            .filter(|enr| {
+                enr.get(ETH2_ENR_KEY)
+                    .map_or(false, |eth2| eth2.starts_with(&fork_digest))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Records the `outcome` of a dial attempt to `peer_id`.
+    ///
+    /// If attempts are already remembered for `MAX_DIAL_HISTORY_PEERS` other peers, the peer whose
+    /// latest attempt is the oldest is forgotten.
+    fn record_dial(&mut self, peer_id: PeerId, outcome: DialOutcome) {
+        if !self.dial_history.contains_key(&peer_id)
+            && self.dial_history.len() >= MAX_DIAL_HISTORY_PEERS
+        {
+            let oldest = self
+                .dial_history
+                .iter()
+                .min_by_key(|(_, attempts)| attempts.back().map(|attempt| attempt.time))
+                .map(|(peer_id, _)| peer_id.clone());
+            if let Some(oldest) = oldest {
+                self.dial_history.remove(&oldest);
+            }
+        }
+
+        let attempts = self.dial_history.entry(peer_id).or_default();
+        attempts.push_back(DialAttempt {
+            time: SystemTime::now(),
+            outcome,
+        });
+        while attempts.len() > MAX_DIAL_HISTORY {
+            attempts.pop_front();
+        }
+    }
+
+    /// Returns an action dialing the next queued peer, unless `max_concurrent_dials` are already
+    /// in flight. Queued peers which have since connected, or are no longer needed, are dropped.
+    fn next_dial<TInEvent, TOutEvent>(
+        &mut self,
+    ) -> Option<NetworkBehaviourAction<TInEvent, TOutEvent>> {
+        while self.dialing.len() < self.max_concurrent_dials {
+            let peer_id = self.dial_queue.pop_front()?;
+            if self.connected_peers.len() < self.max_peers
+                && !self.connected_peers.contains(&peer_id)
+            {
+                self.dialing.insert(peer_id.clone());
+                return Some(NetworkBehaviourAction::DialPeer { peer_id });
+            }
+        }
+        None
+    }
+
+    /// Records `enr` as the most recently seen peer, evicting the oldest if the cache is full.
+    fn record_seen_enr(&mut self, enr: Enr) {
+        self.seen_enrs
+            .retain(|existing| existing.node_id() != enr.node_id());
+        self.seen_enrs.push_front(enr);
+        self.seen_enrs.truncate(MAX_PERSISTED_PEERS);
+    }
+
     /// Search for new peers using the underlying discovery mechanism.
     fn find_peers(&mut self) {
+        let discovery = match self.discovery.as_mut() {
+            Some(discovery) => discovery,
+            None => return,
+        };
+
         // pick a random NodeId
         let random_node = NodeId::random();
         debug!(self.log, "Searching for peers");
-        self.discovery.find_node(random_node);
+        discovery.find_node(random_node);
 
         // update the time until next discovery
         let delay = {
@@ -155,20 +407,43 @@ impl<TSubstream> NetworkBehaviour for Discovery<TSubstream>
 where
     TSubstream: AsyncRead + AsyncWrite,
 {
-    type ProtocolsHandler = <Discv5<TSubstream> as NetworkBehaviour>::ProtocolsHandler;
+    // discv5 communicates over its own UDP socket, so it has no libp2p substreams to handle.
+    type ProtocolsHandler = DummyProtocolsHandler<TSubstream>;
     type OutEvent = <Discv5<TSubstream> as NetworkBehaviour>::OutEvent;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
-        NetworkBehaviour::new_handler(&mut self.discovery)
+        DummyProtocolsHandler::default()
     }
 
     fn addresses_of_peer(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
         // Let discovery track possible known peers.
-        self.discovery.addresses_of_peer(peer_id)
+        match self.discovery.as_mut() {
+            Some(discovery) => discovery.addresses_of_peer(peer_id),
+            None => Vec::new(),
+        }
     }
 
-    fn inject_connected(&mut self, peer_id: PeerId, _endpoint: ConnectedPoint) {
+    fn inject_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
+        // A successful connection makes a peer the most recently seen.
+        if let Some(enr) = self
+            .seen_enrs
+            .iter()
+            .find(|enr| enr.peer_id() == peer_id)
+            .cloned()
+        {
+            self.record_seen_enr(enr);
+        }
+
+        if let ConnectedPoint::Dialer { .. } = endpoint {
+            self.outbound_peers.insert(peer_id.clone());
+            self.record_dial(peer_id.clone(), DialOutcome::Connected);
+        }
+        self.dialing.remove(&peer_id);
+        self.peer_addresses
+            .insert(peer_id.clone(), remote_address(&endpoint));
         self.connected_peers.insert(peer_id);
+        self.last_peer_connected = Some(Instant::now());
+        self.total_connections += 1;
 
         metrics::inc_counter(&metrics::PEER_CONNECT_EVENT_COUNT);
         metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
@@ -176,6 +451,9 @@ where
 
     fn inject_disconnected(&mut self, peer_id: &PeerId, _endpoint: ConnectedPoint) {
         self.connected_peers.remove(peer_id);
+        self.outbound_peers.remove(peer_id);
+        self.peer_addresses.remove(peer_id);
+        self.last_peer_disconnected = Some(Instant::now());
 
         metrics::inc_counter(&metrics::PEER_DISCONNECT_EVENT_COUNT);
         metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
@@ -183,11 +461,29 @@ where
 
     fn inject_replaced(
         &mut self,
-        _peer_id: PeerId,
+        peer_id: PeerId,
         _closed: ConnectedPoint,
-        _opened: ConnectedPoint,
+        opened: ConnectedPoint,
     ) {
-        // discv5 doesn't implement
+        // the peer's address is now that of the new connection
+        self.peer_addresses.insert(peer_id, remote_address(&opened));
+    }
+
+    fn inject_addr_reach_failure(
+        &mut self,
+        peer_id: Option<&PeerId>,
+        addr: &Multiaddr,
+        error: &dyn std::error::Error,
+    ) {
+        if let Some(peer_id) = peer_id {
+            debug!(self.log, "Failed to dial peer"; "peer_id" => format!("{}", peer_id), "addr" => format!("{}", addr), "error" => format!("{}", error));
+            self.record_dial(peer_id.clone(), DialOutcome::from_error(error));
+        }
+    }
+
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        // Every address failed, making room for another dial.
+        self.dialing.remove(peer_id);
     }
 
     fn inject_node_event(
@@ -208,7 +504,7 @@ where
         >,
     > {
         // search for peers if it is time
-        loop {
+        while self.discovery.is_some() {
             match self.peer_discovery_delay.poll() {
                 Ok(Async::Ready(_)) => {
                     if self.connected_peers.len() < self.max_peers {
@@ -222,21 +518,43 @@ where
             }
         }
 
-        // Poll discovery
+        // write the recently seen peers to disk if it is time
         loop {
-            match self.discovery.poll(params) {
+            match self.peer_persist_delay.poll() {
+                Ok(Async::Ready(_)) => {
+                    save_peers_to_disc(Path::new(&self.enr_dir), &self.seen_enrs, &self.log);
+                    self.peer_persist_delay
+                        .reset(Instant::now() + Duration::from_secs(PEER_PERSIST_INTERVAL));
+                }
+                Ok(Async::NotReady) => break,
+                Err(e) => {
+                    warn!(self.log, "Discovery peer persist timer failed"; "Error" => format!("{:?}", e));
+                    break;
+                }
+            }
+        }
+
+        // dial queued peers as earlier dials complete
+        if let Some(action) = self.next_dial() {
+            return Async::Ready(action);
+        }
+
+        // Poll discovery, unless it is disabled
+        while let Some(discovery) = self.discovery.as_mut() {
+            match discovery.poll(params) {
                 Async::Ready(NetworkBehaviourAction::GenerateEvent(event)) => {
                     match event {
-                        Discv5Event::Discovered(_enr) => {
+                        Discv5Event::Discovered(enr) => {
                             // not concerned about FINDNODE results, rather the result of an entire
-                            // query.
+                            // query. The ENR is cached so it can be used after a restart.
+                            self.record_seen_enr(enr);
                         }
                         Discv5Event::SocketUpdated(socket) => {
                             info!(self.log, "Address updated"; "IP" => format!("{}",socket.ip()));
                             metrics::inc_counter(&metrics::ADDRESS_UPDATE_COUNT);
                             let mut address = Multiaddr::from(socket.ip());
                             address.push(Protocol::Tcp(self.tcp_port));
-                            let enr = self.discovery.local_enr();
+                            let enr = self.local_enr();
                             save_enr_to_disc(Path::new(&self.enr_dir), enr, &self.log);
 
                             return Async::Ready(NetworkBehaviourAction::ReportObservedAddr {
@@ -249,16 +567,19 @@ where
                                 debug!(self.log, "Discovery random query found no peers");
                             }
                             for peer_id in closer_peers {
-                                // if we need more peers, attempt a connection
+                                // if we need more peers, queue a connection attempt
                                 if self.connected_peers.len() < self.max_peers
-                                    && self.connected_peers.get(&peer_id).is_none()
+                                    && !self.connected_peers.contains(&peer_id)
+                                    && !self.dialing.contains(&peer_id)
+                                    && !self.dial_queue.contains(&peer_id)
                                 {
                                     debug!(self.log, "Peer discovered"; "peer_id"=> format!("{:?}", peer_id));
-                                    return Async::Ready(NetworkBehaviourAction::DialPeer {
-                                        peer_id,
-                                    });
+                                    self.dial_queue.push_back(peer_id);
                                 }
                             }
+                            if let Some(action) = self.next_dial() {
+                                return Async::Ready(action);
+                            }
                         }
                         _ => {}
                     }
@@ -285,10 +606,14 @@ fn load_enr(
     // Build the local ENR.
     // Note: Discovery should update the ENR record's IP to the external IP as seen by the
     // majority of our peers.
+    let enr_address = config.enr_address.unwrap_or(config.discovery_address);
+    let enr_tcp_port = config.enr_tcp_port.unwrap_or(config.libp2p_port);
+    let enr_udp_port = config.enr_udp_port.unwrap_or(config.discovery_port);
+
     let mut local_enr = EnrBuilder::new()
-        .ip(config.discovery_address)
-        .tcp(config.libp2p_port)
-        .udp(config.discovery_port)
+        .ip(enr_address)
+        .tcp(enr_tcp_port)
+        .udp(enr_udp_port)
         .build(&local_key)
         .map_err(|e| format!("Could not build Local ENR: {:?}", e))?;
 
@@ -301,9 +626,9 @@ fn load_enr(
                 match Enr::from_str(&enr_string) {
                     Ok(enr) => {
                         if enr.node_id() == local_enr.node_id() {
-                            if enr.ip() == config.discovery_address.into()
-                                && enr.tcp() == Some(config.libp2p_port)
-                                && enr.udp() == Some(config.discovery_port)
+                            if enr.ip() == enr_address.into()
+                                && enr.tcp() == Some(enr_tcp_port)
+                                && enr.udp() == Some(enr_udp_port)
                             {
                                 debug!(log, "ENR loaded from file"; "file" => format!("{:?}", enr_f));
                                 // the stored ENR has the same configuration, use it
@@ -331,6 +656,73 @@ fn load_enr(
     Ok(local_enr)
 }
 
+/// A single attempt to dial a peer.
+#[derive(Debug, Clone)]
+pub struct DialAttempt {
+    pub time: SystemTime,
+    pub outcome: DialOutcome,
+}
+
+/// The outcome of dialing a peer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialOutcome {
+    Connected,
+    /// The dial was abandoned after `connect_timeout_secs`, or the OS timed out the connection.
+    Timeout,
+    Refused,
+    /// Any other failure, such as a failed protocol negotiation.
+    ProtocolError(String),
+}
+
+impl DialOutcome {
+    /// Classifies the `error` reported by libp2p for a failed dial.
+    ///
+    /// Transport errors are usually wrapped, so the chain of sources is searched for an
+    /// `io::Error` before falling back to the error message.
+    fn from_error(error: &dyn std::error::Error) -> Self {
+        let mut source = error.source();
+        while let Some(error) = source {
+            if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+                match io_error.kind() {
+                    ErrorKind::TimedOut => return DialOutcome::Timeout,
+                    ErrorKind::ConnectionRefused => return DialOutcome::Refused,
+                    _ => {}
+                }
+            }
+            source = error.source();
+        }
+
+        let description = error.to_string();
+        let lowercase = description.to_lowercase();
+        if lowercase.contains("timeout") || lowercase.contains("timed out") {
+            DialOutcome::Timeout
+        } else if lowercase.contains("refused") {
+            DialOutcome::Refused
+        } else {
+            DialOutcome::ProtocolError(description)
+        }
+    }
+}
+
+impl std::fmt::Display for DialOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DialOutcome::Connected => write!(f, "connected"),
+            DialOutcome::Timeout => write!(f, "timeout"),
+            DialOutcome::Refused => write!(f, "refused"),
+            DialOutcome::ProtocolError(e) => write!(f, "protocol error: {}", e),
+        }
+    }
+}
+
+/// Returns the address of the remote end of a connection.
+fn remote_address(endpoint: &ConnectedPoint) -> Multiaddr {
+    match endpoint {
+        ConnectedPoint::Dialer { address } => address.clone(),
+        ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr.clone(),
+    }
+}
+
 fn save_enr_to_disc(dir: &Path, enr: &Enr, log: &slog::Logger) {
     let _ = std::fs::create_dir_all(dir);
     match File::create(dir.join(Path::new(ENR_FILENAME)))
@@ -347,3 +739,48 @@ fn save_enr_to_disc(dir: &Path, enr: &Enr, log: &slog::Logger) {
         }
     }
 }
+
+/// Loads the ENRs of peers seen in previous runs from `dir`, most recent first.
+///
+/// A missing or invalid file results in an empty list.
+fn load_peers_from_disc(dir: &Path, log: &slog::Logger) -> VecDeque<Enr> {
+    let peers_f = dir.join(PEERS_FILENAME);
+    let enr_strings: Vec<String> = match File::open(&peers_f) {
+        Ok(file) => match serde_json::from_reader(file) {
+            Ok(enr_strings) => enr_strings,
+            Err(e) => {
+                warn!(log, "Could not decode peers file"; "file" => format!("{:?}", peers_f), "error" => format!("{}", e));
+                return VecDeque::new();
+            }
+        },
+        Err(_) => return VecDeque::new(),
+    };
+
+    let enrs: VecDeque<Enr> = enr_strings
+        .iter()
+        .filter_map(|enr_string| Enr::from_str(enr_string).ok())
+        .take(MAX_PERSISTED_PEERS)
+        .collect();
+
+    debug!(log, "Peers loaded from disk"; "count" => enrs.len());
+    enrs
+}
+
+fn save_peers_to_disc(dir: &Path, enrs: &VecDeque<Enr>, log: &slog::Logger) {
+    let enr_strings: Vec<String> = enrs.iter().map(Enr::to_base64).collect();
+    let _ = std::fs::create_dir_all(dir);
+    match File::create(dir.join(PEERS_FILENAME)).and_then(|f| {
+        serde_json::to_writer(f, &enr_strings)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }) {
+        Ok(_) => {
+            debug!(log, "Peers written to disk"; "count" => enr_strings.len());
+        }
+        Err(e) => {
+            warn!(
+                log,
+                "Could not write peers to file"; "file" => format!("{:?}{:?}", dir, PEERS_FILENAME), "error" => format!("{}", e)
+            );
+        }
+    }
+}
@@ -13,11 +13,15 @@ mod metrics;
 pub mod rpc;
 mod service;
 
-pub use behaviour::PubsubMessage;
+pub use behaviour::{
+    supported_protocols, PeerInfo, PeerScore, PeerState, PubsubMessage, PROTOCOL_VERSION,
+};
 pub use config::{
-    Config as NetworkConfig, BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC, SHARD_TOPIC_PREFIX,
-    TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
+    shard_subnet_topic, subnet_id_from_topic, Config as NetworkConfig, GossipConfig,
+    BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC, MAX_BAN_DURATION, SHARD_SUBNET_COUNT,
+    SHARD_TOPIC_PREFIX, TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
 };
+pub use discovery::{DialAttempt, DialOutcome, ETH2_ENR_KEY, FORK_DIGEST_LEN};
 pub use libp2p::enr::Enr;
 pub use libp2p::gossipsub::{Topic, TopicHash};
 pub use libp2p::multiaddr;
@@ -28,4 +32,4 @@ pub use libp2p::{
 };
 pub use rpc::RPCEvent;
 pub use service::Libp2pEvent;
-pub use service::Service;
+pub use service::{peer_id_from_network_dir, Service};
@@ -8,13 +8,15 @@ use futures::prelude::*;
 use handler::RPCHandler;
 use libp2p::core::ConnectedPoint;
 use libp2p::swarm::{
-    protocols_handler::ProtocolsHandler, NetworkBehaviour, NetworkBehaviourAction, PollParameters,
+    protocols_handler::{ProtocolsHandler, SubstreamProtocol},
+    NetworkBehaviour, NetworkBehaviourAction, PollParameters,
 };
 use libp2p::{Multiaddr, PeerId};
 pub use methods::{ErrorMessage, HelloMessage, RPCErrorResponse, RPCResponse, RequestId};
 pub use protocol::{RPCError, RPCProtocol, RPCRequest};
 use slog::o;
 use std::marker::PhantomData;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 pub(crate) mod codec;
@@ -52,6 +54,8 @@ impl RPCEvent {
 pub struct RPC<TSubstream> {
     /// Queue of events to processed.
     events: Vec<NetworkBehaviourAction<RPCEvent, RPCMessage>>,
+    /// How long a connection handler keeps the connection alive once it has no substreams.
+    inactive_timeout: Duration,
     /// Pins the generic substream.
     marker: PhantomData<(TSubstream)>,
     /// Slog logger for RPC behaviour.
@@ -59,10 +63,11 @@ pub struct RPC<TSubstream> {
 }
 
 impl<TSubstream> RPC<TSubstream> {
-    pub fn new(log: &slog::Logger) -> Self {
+    pub fn new(log: &slog::Logger, inactive_timeout: Duration) -> Self {
         let log = log.new(o!("Service" => "Libp2p-RPC"));
         RPC {
             events: Vec::new(),
+            inactive_timeout,
             marker: PhantomData,
             _log: log,
         }
@@ -87,7 +92,7 @@ where
     type OutEvent = RPCMessage;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
-        Default::default()
+        RPCHandler::new(SubstreamProtocol::new(RPCProtocol), self.inactive_timeout)
     }
 
     // handled by discovery
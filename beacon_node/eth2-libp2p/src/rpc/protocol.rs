@@ -45,6 +45,16 @@ impl UpgradeInfo for RPCProtocol {
     }
 }
 
+impl RPCProtocol {
+    /// Returns the protocol id strings of all supported RPC methods.
+    pub fn protocol_ids(&self) -> Vec<String> {
+        self.protocol_info()
+            .into_iter()
+            .map(|protocol| protocol.protocol_id)
+            .collect()
+    }
+}
+
 /// Tracks the types in a protocol id.
 #[derive(Clone)]
 pub struct ProtocolId {
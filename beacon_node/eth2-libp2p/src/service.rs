@@ -1,4 +1,4 @@
-use crate::behaviour::{Behaviour, BehaviourEvent, PubsubMessage};
+use crate::behaviour::{Behaviour, BehaviourEvent, PubsubMessage, SCORE_BAN_DURATION};
 use crate::config::*;
 use crate::error;
 use crate::multiaddr::Protocol;
@@ -20,12 +20,19 @@ use slog::{crit, debug, info, trace, warn};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind};
-use std::time::Duration;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 type Libp2pStream = Boxed<(PeerId, StreamMuxerBox), Error>;
 type Libp2pBehaviour = Behaviour<Substream<StreamMuxerBox>>;
 
 const NETWORK_KEY_FILENAME: &str = "key";
+/// The permissions of a directory accessible only by its owner.
+const PRIVATE_DIR_MODE: u32 = 0o700;
+/// The permissions of a file readable and writable only by its owner.
+const PRIVATE_FILE_MODE: u32 = 0o600;
+/// A file which is briefly created to check that the network dir is writable.
+const WRITE_TEST_FILENAME: &str = ".write_test";
 
 /// The configuration and state of the libp2p components for the beacon node.
 pub struct Service {
@@ -34,6 +41,8 @@ pub struct Service {
     pub swarm: Swarm<Libp2pStream, Libp2pBehaviour>,
     /// This node's PeerId.
     pub local_peer_id: PeerId,
+    /// The gossipsub topics this node successfully subscribed to at startup.
+    pub subscribed_topics: Vec<Topic>,
     /// The libp2p logger handle.
     pub log: slog::Logger,
 }
@@ -42,6 +51,17 @@ impl Service {
     pub fn new(config: NetworkConfig, log: slog::Logger) -> error::Result<Self> {
         trace!(log, "Libp2p Service starting");
 
+        // the network key and peer cache are stored in the network dir
+        ensure_dir_is_writable(&config.network_dir)?;
+        if config.restrict_permissions {
+            restrict_permissions(&config.network_dir, PRIVATE_DIR_MODE).map_err(|e| {
+                format!(
+                    "Unable to restrict permissions of network dir {:?}: {}",
+                    config.network_dir, e
+                )
+            })?;
+        }
+
         // load the private key from CLI flag, disk or generate a new one
         let local_private_key = load_private_key(&config, &log);
         let local_peer_id = PeerId::from(local_private_key.public());
@@ -49,35 +69,42 @@ impl Service {
 
         let mut swarm = {
             // Set up the transport - tcp/ws with secio and mplex/yamux
-            let transport = build_transport(local_private_key.clone());
+            let transport = build_transport(
+                local_private_key.clone(),
+                Duration::from_secs(config.connect_timeout_secs),
+            );
             // Lighthouse network behaviour
             let behaviour = Behaviour::new(&local_private_key, &config, &log)?;
             Swarm::new(transport, behaviour, local_peer_id.clone())
         };
 
-        // listen on the specified address
-        let listen_multiaddr = {
-            let mut m = Multiaddr::from(config.listen_address);
-            m.push(Protocol::Tcp(config.libp2p_port));
-            m
-        };
+        // listen on the specified addresses
+        let listen_addresses =
+            std::iter::once(config.listen_address).chain(config.extra_listen_addresses.clone());
+        for listen_address in listen_addresses {
+            let listen_multiaddr = {
+                let mut m = Multiaddr::from(listen_address);
+                m.push(Protocol::Tcp(config.libp2p_port));
+                m
+            };
 
-        match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
-            Ok(_) => {
-                let mut log_address = listen_multiaddr;
-                log_address.push(Protocol::P2p(local_peer_id.clone().into()));
-                info!(log, "Listening established"; "address" => format!("{}", log_address));
-            }
-            Err(err) => {
-                crit!(
-                    log,
-                    "Unable to listen on libp2p address";
-                    "error" => format!("{:?}", err),
-                    "listen_multiaddr" => format!("{}", listen_multiaddr),
-                );
-                return Err("Libp2p was unable to listen on the given listen address.".into());
-            }
-        };
+            match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
+                Ok(_) => {
+                    let mut log_address = listen_multiaddr;
+                    log_address.push(Protocol::P2p(local_peer_id.clone().into()));
+                    info!(log, "Listening established"; "address" => format!("{}", log_address));
+                }
+                Err(err) => {
+                    crit!(
+                        log,
+                        "Unable to listen on libp2p address";
+                        "error" => format!("{:?}", err),
+                        "listen_multiaddr" => format!("{}", listen_multiaddr),
+                    );
+                    return Err("Libp2p was unable to listen on the given listen address.".into());
+                }
+            };
+        }
 
         // attempt to connect to user-input libp2p nodes
         for multiaddr in config.libp2p_nodes {
@@ -109,6 +136,14 @@ impl Service {
         topics.push(topic_builder(PROPOSER_SLASHING_TOPIC));
         topics.push(topic_builder(ATTESTER_SLASHING_TOPIC));
 
+        // Subscribe to all attestation subnets if requested. This is intended for nodes that
+        // relay or index attestations, and significantly increases bandwidth usage.
+        if config.subscribe_all_subnets {
+            for subnet in 0..SHARD_SUBNET_COUNT {
+                topics.push(Topic::new(shard_subnet_topic(subnet)));
+            }
+        }
+
         // Add any topics specified by the user
         topics.append(
             &mut config
@@ -132,17 +167,57 @@ impl Service {
 
         Ok(Service {
             local_peer_id,
+            subscribed_topics,
             swarm,
             log,
         })
     }
 }
 
+impl Service {
+    /// Disconnects `peer_id` and refuses connections to or from it for `duration`, recording
+    /// `reason` for the ban.
+    ///
+    /// Returns the time at which the ban expires. A `duration` longer than `MAX_BAN_DURATION` is
+    /// shortened to it.
+    pub fn disconnect_and_ban_peer(
+        &mut self,
+        peer_id: PeerId,
+        duration: Duration,
+        reason: String,
+    ) -> SystemTime {
+        let expiry = SystemTime::now() + duration.min(MAX_BAN_DURATION);
+        self.swarm.record_ban(peer_id.clone(), expiry, reason);
+        Swarm::ban_peer_id(&mut self.swarm, peer_id);
+        expiry
+    }
+
+    /// Lifts the ban on `peer_id`, returning `false` if it was not banned.
+    pub fn unban_peer(&mut self, peer_id: PeerId) -> bool {
+        let was_banned = self.swarm.remove_ban(&peer_id);
+        Swarm::unban_peer_id(&mut self.swarm, peer_id);
+        was_banned
+    }
+}
+
 impl Stream for Service {
     type Item = Libp2pEvent;
     type Error = crate::error::Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        for peer_id in self.swarm.take_peers_to_ban() {
+            warn!(self.log, "Banning peer due to low score"; "peer_id" => format!("{}", peer_id));
+            self.disconnect_and_ban_peer(
+                peer_id,
+                SCORE_BAN_DURATION,
+                "Score fell below the ban threshold".to_string(),
+            );
+        }
+        for peer_id in self.swarm.take_expired_bans() {
+            debug!(self.log, "Peer ban expired"; "peer_id" => format!("{}", peer_id));
+            Swarm::unban_peer_id(&mut self.swarm, peer_id);
+        }
+
         loop {
             match self.swarm.poll() {
                 //Behaviour events
@@ -181,7 +256,12 @@ impl Stream for Service {
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, secio as the encryption layer, and
 /// mplex or yamux as the multiplexing layer.
-fn build_transport(local_private_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox), Error> {
+/// Builds the libp2p transport, abandoning any connection that is not established and upgraded
+/// within `timeout`.
+fn build_transport(
+    local_private_key: Keypair,
+    timeout: Duration,
+) -> Boxed<(PeerId, StreamMuxerBox), Error> {
     // TODO: The Wire protocol currently doesn't specify encryption and this will need to be customised
     // in the future.
     let transport = libp2p::tcp::TcpConfig::new();
@@ -207,7 +287,7 @@ fn build_transport(local_private_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)
             core::upgrade::apply(out.stream, upgrade, endpoint)
                 .map(|(id, muxer)| (id, core::muxing::StreamMuxerBox::new(muxer)))
         })
-        .with_timeout(Duration::from_secs(20))
+        .with_timeout(timeout)
         .map_err(|err| Error::new(ErrorKind::Other, err))
         .boxed()
 }
@@ -228,6 +308,63 @@ pub enum Libp2pEvent {
     },
 }
 
+/// Creates `dir` if it does not exist and returns an error if files cannot be written to it.
+fn ensure_dir_is_writable(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Unable to create network dir {:?}: {}", dir, e))?;
+
+    let test_file = dir.join(WRITE_TEST_FILENAME);
+    File::create(&test_file)
+        .and_then(|mut f| f.write_all(&[]))
+        .and_then(|_| std::fs::remove_file(&test_file))
+        .map_err(|e| format!("Network dir {:?} is not writable: {}", dir, e))
+}
+
+/// Sets the Unix permission bits of `path` to `mode`. A no-op on other platforms.
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Returns `true` if `file` may be read or written by users other than its owner. Always `false`
+/// on platforms other than Unix.
+#[cfg(unix)]
+fn is_readable_by_others(file: &File) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    file.metadata()
+        .map(|metadata| metadata.permissions().mode() & 0o077 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_readable_by_others(_file: &File) -> bool {
+    false
+}
+
+/// Returns the `PeerId` of the network key stored in `network_dir`, or `None` if there is no key.
+///
+/// Never generates or writes a key.
+pub fn peer_id_from_network_dir(network_dir: &Path) -> Result<Option<PeerId>, String> {
+    let network_key_f = network_dir.join(NETWORK_KEY_FILENAME);
+    let mut key_bytes = match std::fs::read(&network_key_f) {
+        Ok(bytes) => bytes,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Unable to read {:?}: {}", network_key_f, e)),
+    };
+
+    let secret_key = libp2p::core::identity::secp256k1::SecretKey::from_bytes(&mut key_bytes)
+        .map_err(|_| format!("{:?} is not a valid secp256k1 key", network_key_f))?;
+    let keypair: libp2p::core::identity::secp256k1::Keypair = secret_key.into();
+
+    Ok(Some(PeerId::from(Keypair::Secp256k1(keypair).public())))
+}
+
 /// Loads a private key from disk. If this fails, a new key is
 /// generated and is then saved to disk.
 ///
@@ -237,6 +374,15 @@ fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> Keypair {
     // check for key from disk
     let network_key_f = config.network_dir.join(NETWORK_KEY_FILENAME);
     if let Ok(mut network_key_file) = File::open(network_key_f.clone()) {
+        if config.restrict_permissions && is_readable_by_others(&network_key_file) {
+            warn!(
+                log,
+                "Network key file is readable by other users";
+                "file" => format!("{:?}", network_key_f),
+                "fix" => format!("chmod 600 {:?}", network_key_f),
+            );
+        }
+
         let mut key_bytes: Vec<u8> = Vec::with_capacity(36);
         match network_key_file.read_to_end(&mut key_bytes) {
             Err(_) => debug!(log, "Could not read network key file"),
@@ -260,6 +406,12 @@ fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> Keypair {
     if let Keypair::Secp256k1(key) = local_private_key.clone() {
         let _ = std::fs::create_dir_all(&config.network_dir);
         match File::create(network_key_f.clone())
+            .and_then(|f| {
+                if config.restrict_permissions {
+                    restrict_permissions(&network_key_f, PRIVATE_FILE_MODE)?;
+                }
+                Ok(f)
+            })
             .and_then(|mut f| f.write_all(&key.secret().to_bytes()))
         {
             Ok(_) => {
@@ -1,6 +1,7 @@
 /// This crate provides the network server for Lighthouse.
 pub mod error;
 pub mod message_handler;
+mod nat;
 pub mod service;
 pub mod sync;
 
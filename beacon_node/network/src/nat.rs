@@ -0,0 +1,206 @@
+//! Forwards the ports of the node with UPnP, so that peers outside a home router can dial it.
+use crate::NetworkConfig;
+use igd::{Gateway, PortMappingProtocol, SearchOptions};
+use slog::{debug, info, warn};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// The description of the port mappings, as shown by the router.
+const MAPPING_DESCRIPTION: &str = "lighthouse";
+/// The lease of a port mapping in seconds, after which the router removes it unless renewed.
+const MAPPING_LEASE_SECS: u32 = 3600;
+/// How often the port mappings are renewed, leaving half the lease to spare.
+const MAPPING_RENEWAL_INTERVAL: Duration = Duration::from_secs(MAPPING_LEASE_SECS as u64 / 2);
+/// How long to wait for a router to answer the gateway search, which delays startup.
+const GATEWAY_SEARCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The ports mapped by `forward_ports`, which are removed from the router when this is dropped.
+pub struct PortMappings {
+    gateway: Gateway,
+    mappings: Vec<(PortMappingProtocol, SocketAddrV4)>,
+    log: slog::Logger,
+}
+
+impl PortMappings {
+    /// Maps each port again, extending its lease.
+    fn renew(&self) {
+        for (protocol, local_addr) in &self.mappings {
+            add_port(&self.gateway, *protocol, *local_addr, &self.log);
+        }
+    }
+}
+
+impl Drop for PortMappings {
+    fn drop(&mut self) {
+        for (protocol, local_addr) in &self.mappings {
+            match self.gateway.remove_port(*protocol, local_addr.port()) {
+                Ok(()) => {
+                    debug!(self.log, "UPnP port mapping removed"; "protocol" => format!("{:?}", protocol), "port" => local_addr.port())
+                }
+                Err(e) => {
+                    warn!(self.log, "Unable to remove UPnP port mapping"; "protocol" => format!("{:?}", protocol), "port" => local_addr.port(), "error" => format!("{}", e))
+                }
+            }
+        }
+    }
+}
+
+/// Forwards the libp2p TCP port and the discovery UDP port of `config` to this host with UPnP.
+///
+/// Each port is mapped to the same external port. If every port is mapped and no ENR address is
+/// configured, the external IP address of the router is set as the ENR address. Failures are
+/// logged and otherwise ignored, since most nodes work without forwarding.
+///
+/// The mappings are renewed until the returned `PortMappings` is dropped.
+pub fn forward_ports(config: &mut NetworkConfig, log: &slog::Logger) -> Option<Arc<PortMappings>> {
+    if config.listen_address.is_loopback() {
+        warn!(
+            log,
+            "Not forwarding ports with UPnP";
+            "reason" => "the listen address is a loopback address",
+            "listen_address" => format!("{}", config.listen_address),
+        );
+        return None;
+    }
+
+    let search_options = SearchOptions {
+        timeout: Some(GATEWAY_SEARCH_TIMEOUT),
+        ..SearchOptions::default()
+    };
+    let gateway = match igd::search_gateway(search_options) {
+        Ok(gateway) => gateway,
+        Err(e) => {
+            warn!(log, "UPnP gateway not found"; "error" => format!("{}", e));
+            return None;
+        }
+    };
+
+    let mut mappings = vec![];
+    let mut all_mapped = true;
+
+    match local_ip(config.listen_address, &gateway) {
+        Ok(local_ip) => {
+            let local_addr = SocketAddrV4::new(local_ip, config.libp2p_port);
+            if add_port(&gateway, PortMappingProtocol::TCP, local_addr, log) {
+                mappings.push((PortMappingProtocol::TCP, local_addr));
+            } else {
+                all_mapped = false;
+            }
+        }
+        Err(e) => {
+            warn!(log, "Unable to determine the local libp2p address for UPnP"; "error" => e);
+            all_mapped = false;
+        }
+    }
+
+    if !config.disable_discovery {
+        // discv5 binds to the UDP port of the local ENR.
+        let discovery_port = config.enr_udp_port.unwrap_or(config.discovery_port);
+        match local_ip(config.discovery_address, &gateway) {
+            Ok(local_ip) => {
+                let local_addr = SocketAddrV4::new(local_ip, discovery_port);
+                if add_port(&gateway, PortMappingProtocol::UDP, local_addr, log) {
+                    mappings.push((PortMappingProtocol::UDP, local_addr));
+                } else {
+                    all_mapped = false;
+                }
+            }
+            Err(e) => {
+                warn!(log, "Unable to determine the local discovery address for UPnP"; "error" => e);
+                all_mapped = false;
+            }
+        }
+    }
+
+    // Peers could dial the external address but not reach one of the ports, so it is only
+    // published if both are reachable.
+    if all_mapped && config.enr_address.is_none() {
+        match gateway.get_external_ip() {
+            Ok(external_ip) => {
+                info!(log, "Publishing the UPnP external address"; "address" => format!("{}", external_ip));
+                config.enr_address = Some(IpAddr::V4(external_ip));
+            }
+            Err(e) => {
+                warn!(log, "Unable to read the UPnP external address"; "error" => format!("{}", e))
+            }
+        }
+    }
+
+    if mappings.is_empty() {
+        return None;
+    }
+
+    let mappings = Arc::new(PortMappings {
+        gateway,
+        mappings,
+        log: log.clone(),
+    });
+    renew_until_dropped(Arc::downgrade(&mappings));
+    Some(mappings)
+}
+
+/// Renews `mappings` every `MAPPING_RENEWAL_INTERVAL` on a new thread, until they are dropped.
+fn renew_until_dropped(mappings: Weak<PortMappings>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(MAPPING_RENEWAL_INTERVAL);
+        match mappings.upgrade() {
+            Some(mappings) => mappings.renew(),
+            None => return,
+        }
+    });
+}
+
+/// Maps the port of `local_addr` on the router to `local_addr`, returning `true` if successful.
+fn add_port(
+    gateway: &Gateway,
+    protocol: PortMappingProtocol,
+    local_addr: SocketAddrV4,
+    log: &slog::Logger,
+) -> bool {
+    let port = local_addr.port();
+    match gateway.add_port(
+        protocol,
+        port,
+        local_addr,
+        MAPPING_LEASE_SECS,
+        MAPPING_DESCRIPTION,
+    ) {
+        Ok(()) => {
+            info!(log, "UPnP port mapped"; "protocol" => format!("{:?}", protocol), "port" => port);
+            true
+        }
+        Err(e) => {
+            warn!(log, "UPnP port mapping failed"; "protocol" => format!("{:?}", protocol), "port" => port, "error" => format!("{}", e));
+            false
+        }
+    }
+}
+
+/// Returns the IPv4 address of this host on the network of `gateway` for a socket bound to
+/// `bind_address`.
+///
+/// This is `bind_address` if it is specific, otherwise the address the OS routes to the gateway
+/// from. A loopback `bind_address` cannot be reached from the router, so it is an error.
+fn local_ip(bind_address: IpAddr, gateway: &Gateway) -> Result<Ipv4Addr, String> {
+    match bind_address {
+        IpAddr::V4(ip) if ip.is_loopback() => {
+            return Err(format!("{} is a loopback address", ip));
+        }
+        IpAddr::V4(ip) if !ip.is_unspecified() => return Ok(ip),
+        IpAddr::V4(_) => {}
+        IpAddr::V6(_) => return Err("UPnP requires an IPv4 address".into()),
+    }
+
+    // Connecting a UDP socket sends nothing, it only selects the route.
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Unable to bind a UDP socket: {}", e))?;
+    socket
+        .connect(gateway.addr)
+        .map_err(|e| format!("Unable to route to the gateway: {}", e))?;
+    match socket.local_addr() {
+        Ok(SocketAddr::V4(addr)) => Ok(*addr.ip()),
+        Ok(SocketAddr::V6(_)) => Err("The route to the gateway is not IPv4".into()),
+        Err(e) => Err(format!("Unable to read the local address: {}", e)),
+    }
+}
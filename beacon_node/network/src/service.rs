@@ -1,24 +1,37 @@
 use crate::error;
 use crate::message_handler::{HandlerMessage, MessageHandler};
+use crate::nat;
+use crate::sync::PeerHeadSlots;
 use crate::NetworkConfig;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use core::marker::PhantomData;
 use eth2_libp2p::Service as LibP2PService;
 use eth2_libp2p::Topic;
-use eth2_libp2p::{Enr, Libp2pEvent, Multiaddr, PeerId, Swarm};
+use eth2_libp2p::{
+    subnet_id_from_topic, DialAttempt, Enr, GossipConfig, Libp2pEvent, Multiaddr, PeerId,
+    PeerScore, Swarm,
+};
 use eth2_libp2p::{PubsubMessage, RPCEvent};
 use futures::prelude::*;
 use futures::Stream;
 use parking_lot::Mutex;
 use slog::{debug, info, o, trace};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::runtime::TaskExecutor;
 use tokio::sync::{mpsc, oneshot};
+use types::Slot;
 
 /// Service that handles communication between internal services and the eth2_libp2p network service.
 pub struct Service<T: BeaconChainTypes> {
     libp2p_service: Arc<Mutex<LibP2PService>>,
-    libp2p_port: u16,
+    /// The configuration the libp2p service was started with.
+    config: NetworkConfig,
+    agent_version: String,
+    peer_head_slots: PeerHeadSlots,
+    /// The UPnP port mappings, which are removed from the router when the service is dropped.
+    _port_mappings: Option<Arc<nat::PortMappings>>,
     _libp2p_exit: oneshot::Sender<()>,
     _network_send: mpsc::UnboundedSender<NetworkMessage>,
     _phantom: PhantomData<T>,
@@ -35,14 +48,25 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
         let (network_send, network_recv) = mpsc::unbounded_channel::<NetworkMessage>();
         // launch message handler thread
         let message_handler_log = log.new(o!("Service" => "MessageHandler"));
+        let peer_head_slots = PeerHeadSlots::default();
         let message_handler_send = MessageHandler::spawn(
             beacon_chain,
             network_send.clone(),
+            peer_head_slots.clone(),
             executor,
             message_handler_log,
         )?;
 
         let network_log = log.new(o!("Service" => "Network"));
+
+        // The ports must be forwarded before the ENR is built, so that it has the external IP.
+        let mut config = config.clone();
+        let port_mappings = if config.upnp_enabled {
+            nat::forward_ports(&mut config, &network_log)
+        } else {
+            None
+        };
+
         // launch libp2p service
         let libp2p_service = Arc::new(Mutex::new(LibP2PService::new(
             config.clone(),
@@ -58,7 +82,10 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
         )?;
         let network_service = Service {
             libp2p_service,
-            libp2p_port: config.libp2p_port,
+            agent_version: config.agent_version(),
+            config,
+            peer_head_slots,
+            _port_mappings: port_mappings,
             _libp2p_exit: libp2p_exit,
             _network_send: network_send.clone(),
             _phantom: PhantomData,
@@ -92,7 +119,7 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
 
     /// Returns the libp2p port that this node has been configured to listen using.
     pub fn listen_port(&self) -> u16 {
-        self.libp2p_port
+        self.config.libp2p_port
     }
 
     /// Returns the number of libp2p connected peers.
@@ -100,6 +127,45 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
         self.libp2p_service.lock().swarm.connected_peers()
     }
 
+    /// Returns the number of connected peers below which the node is not considered ready.
+    pub fn minimum_peers(&self) -> usize {
+        self.config.minimum_peers
+    }
+
+    /// Returns the number of libp2p connected peers that were dialed by this node.
+    pub fn connected_outbound_peers(&self) -> usize {
+        self.libp2p_service
+            .lock()
+            .swarm
+            .discovery()
+            .outbound_peers()
+    }
+
+    /// Returns `true` if discv5 peer discovery is enabled.
+    pub fn discovery_enabled(&self) -> bool {
+        self.libp2p_service.lock().swarm.discovery().is_enabled()
+    }
+
+    /// Starts a discv5 peer search, returning the number of recently seen peer ENRs at the time of
+    /// the request.
+    ///
+    /// Returns the time until another search may be requested if one was requested too recently.
+    pub fn request_peer_search(&self) -> Result<usize, Duration> {
+        let mut libp2p = self.libp2p_service.lock();
+        let discovery = libp2p.swarm.discovery_mut();
+        discovery.request_peer_search()?;
+        Ok(discovery.seen_enr_count())
+    }
+
+    /// Returns the number of libp2p connected peers that dialed this node.
+    pub fn connected_inbound_peers(&self) -> usize {
+        let libp2p = self.libp2p_service.lock();
+        let discovery = libp2p.swarm.discovery();
+        discovery
+            .connected_peers()
+            .saturating_sub(discovery.outbound_peers())
+    }
+
     /// Returns the set of `PeerId` that are connected via libp2p.
     pub fn connected_peer_set(&self) -> Vec<PeerId> {
         self.libp2p_service
@@ -112,12 +178,245 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
             .collect()
     }
 
+    /// Returns the agent version of each connected peer, or `None` if the peer has not (yet)
+    /// identified itself.
+    pub fn peer_agents(&self) -> HashMap<PeerId, Option<String>> {
+        let libp2p = self.libp2p_service.lock();
+        let peer_info = libp2p.swarm.peer_info();
+        libp2p
+            .swarm
+            .discovery()
+            .connected_peer_set()
+            .iter()
+            .map(|peer_id| {
+                let agent = peer_info
+                    .get(peer_id)
+                    .and_then(|info| info.agent_version.clone());
+                (peer_id.clone(), agent)
+            })
+            .collect()
+    }
+
+    /// Returns the protocols `peer_id` advertised when it identified itself, which is empty if it
+    /// has not (yet) done so.
+    pub fn peer_protocols(&self, peer_id: &PeerId) -> Vec<String> {
+        self.libp2p_service
+            .lock()
+            .swarm
+            .peer_info()
+            .get(peer_id)
+            .map(|info| info.protocols.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the round-trip time of the last successful ping to each connected peer, or `None`
+    /// if the peer has not (yet) been pinged.
+    pub fn peer_latencies(&self) -> HashMap<PeerId, Option<Duration>> {
+        let libp2p = self.libp2p_service.lock();
+        let peer_info = libp2p.swarm.peer_info();
+        libp2p
+            .swarm
+            .discovery()
+            .connected_peer_set()
+            .iter()
+            .map(|peer_id| {
+                let latency = peer_info.get(peer_id).and_then(|info| info.latency);
+                (peer_id.clone(), latency)
+            })
+            .collect()
+    }
+
+    /// Returns the highest head slot advertised by any connected peer, if any peer has completed
+    /// a `Hello` handshake.
+    pub fn highest_peer_head_slot(&self) -> Option<Slot> {
+        self.peer_head_slots.read().values().max().cloned()
+    }
+
+    /// Returns the score and state of each connected peer, as well as any banned peers.
+    pub fn peer_scores(&self) -> HashMap<PeerId, PeerScore> {
+        self.libp2p_service.lock().swarm.peer_scores()
+    }
+
+    /// Disconnects `peer_id` and refuses connections to or from it for `duration`, or the
+    /// configured default ban duration if `None`.
+    ///
+    /// Returns the time at which the ban expires.
+    pub fn ban_peer(
+        &self,
+        peer_id: PeerId,
+        duration: Option<Duration>,
+        reason: String,
+    ) -> SystemTime {
+        self.libp2p_service.lock().disconnect_and_ban_peer(
+            peer_id,
+            duration.unwrap_or_else(|| Duration::from_secs(self.config.ban_duration_secs)),
+            reason,
+        )
+    }
+
+    /// Returns the currently banned peers.
+    pub fn banned_peers(&self) -> HashMap<PeerId, PeerScore> {
+        self.libp2p_service.lock().swarm.banned_peers().clone()
+    }
+
+    /// Lifts the ban on `peer_id`, returning `false` if it was not banned.
+    pub fn unban_peer(&self, peer_id: PeerId) -> bool {
+        self.libp2p_service.lock().unban_peer(peer_id)
+    }
+
+    /// Returns the remote address of each connected peer.
+    pub fn peer_addresses(&self) -> HashMap<PeerId, Multiaddr> {
+        self.libp2p_service
+            .lock()
+            .swarm
+            .discovery()
+            .peer_addresses()
+            .clone()
+    }
+
+    /// Returns the most recent dial attempts to `peer_id`, oldest first.
+    pub fn dial_history(&self, peer_id: &PeerId) -> Vec<DialAttempt> {
+        self.libp2p_service
+            .lock()
+            .swarm
+            .discovery()
+            .dial_history()
+            .get(peer_id)
+            .map_or_else(Vec::new, |attempts| attempts.iter().cloned().collect())
+    }
+
+    /// Returns counters describing how this node has gained and lost peers since startup.
+    pub fn connection_stats(&self) -> ConnectionStats {
+        let libp2p = self.libp2p_service.lock();
+        let discovery = libp2p.swarm.discovery();
+        ConnectionStats {
+            connected_peers: discovery.connected_peers(),
+            target_peers: discovery.max_peers(),
+            since_last_peer_connected: discovery.last_peer_connected().map(|t| t.elapsed()),
+            since_last_peer_disconnected: discovery.last_peer_disconnected().map(|t| t.elapsed()),
+            total_connections: discovery.total_connections(),
+            dials_in_flight: discovery.dials_in_flight(),
+            queued_dials: discovery.queued_dials(),
+        }
+    }
+
+    /// Returns the recently discovered ENRs advertising `fork_digest`.
+    pub fn enrs_with_fork_digest(&self, fork_digest: [u8; FORK_DIGEST_LEN]) -> Vec<Enr> {
+        self.libp2p_service
+            .lock()
+            .swarm
+            .discovery()
+            .enrs_with_fork_digest(fork_digest)
+    }
+
+    /// Returns the gossipsub topics this node is subscribed to.
+    pub fn gossip_topics(&self) -> Vec<String> {
+        self.libp2p_service
+            .lock()
+            .subscribed_topics
+            .iter()
+            .map(|topic| format!("{}", topic))
+            .collect()
+    }
+
+    /// Returns the connected peers that have announced a subscription to each gossipsub topic this
+    /// node is subscribed to, keyed by topic.
+    pub fn gossip_mesh(&self) -> HashMap<String, Vec<PeerId>> {
+        let libp2p = self.libp2p_service.lock();
+        let mut topic_peers = libp2p.swarm.topic_peers();
+        libp2p
+            .subscribed_topics
+            .iter()
+            .map(|topic| {
+                let peers = topic_peers.remove(&topic.no_hash()).unwrap_or_default();
+                (format!("{}", topic), peers)
+            })
+            .collect()
+    }
+
+    /// Returns the ids of the attestation subnets whose topics are subscribed to, in ascending
+    /// order.
+    pub fn subnet_subscriptions(&self) -> Vec<u64> {
+        let mut subnet_ids: Vec<u64> = self
+            .libp2p_service
+            .lock()
+            .subscribed_topics
+            .iter()
+            .filter_map(|topic| subnet_id_from_topic(&format!("{}", topic)))
+            .collect();
+        subnet_ids.sort_unstable();
+        subnet_ids.dedup();
+        subnet_ids
+    }
+
+    /// Returns the configuration the network service was started with, including any changes made
+    /// at startup, e.g., by `--zero-ports`.
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    /// Returns the boot node ENRs the node was configured with.
+    pub fn boot_nodes(&self) -> &[Enr] {
+        &self.config.boot_nodes
+    }
+
+    /// Returns the multiaddrs the node was configured to dial at startup.
+    pub fn libp2p_nodes(&self) -> &[Multiaddr] {
+        &self.config.libp2p_nodes
+    }
+
+    /// Returns the agent string sent to peers via identify.
+    pub fn agent_version(&self) -> &str {
+        &self.agent_version
+    }
+
+    /// Returns the operator-chosen name included in the agent string, if any.
+    pub fn moniker(&self) -> Option<&str> {
+        self.config.moniker.as_ref().map(String::as_str)
+    }
+
+    /// Returns `true` if the node is configured as a bootnode.
+    pub fn is_bootnode(&self) -> bool {
+        self.config.bootnode
+    }
+
+    /// Returns `true` if the node relays the traffic of every attestation subnet, i.e., it was
+    /// configured to subscribe to all subnets.
+    pub fn relay_enabled(&self) -> bool {
+        self.config.subscribe_all_subnets
+    }
+
+    /// Returns the gossipsub mesh parameters in use.
+    pub fn gossip_config(&self) -> &GossipConfig {
+        &self.config.gossip
+    }
+
+    /// Returns the ids of the stream protocols this node offers to peers.
+    pub fn supported_protocols(&self) -> Vec<String> {
+        eth2_libp2p::supported_protocols()
+    }
+
     /// Provides a reference to the underlying libp2p service.
     pub fn libp2p_service(&self) -> Arc<Mutex<LibP2PService>> {
         self.libp2p_service.clone()
     }
 }
 
+/// Counters describing how a node has gained and lost peers since startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionStats {
+    pub connected_peers: usize,
+    pub target_peers: usize,
+    /// The time since a peer last connected, or `None` if no peer has.
+    pub since_last_peer_connected: Option<Duration>,
+    /// The time since a peer last disconnected, or `None` if no peer has.
+    pub since_last_peer_disconnected: Option<Duration>,
+    pub total_connections: u64,
+    pub dials_in_flight: usize,
+    /// Discovered peers waiting for a dial to complete before they are dialed.
+    pub queued_dials: usize,
+}
+
 fn spawn_service(
     libp2p_service: Arc<Mutex<LibP2PService>>,
     network_recv: mpsc::UnboundedReceiver<NetworkMessage>,
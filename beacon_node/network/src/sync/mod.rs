@@ -4,7 +4,7 @@ mod manager;
 /// Stores the various syncing methods for the beacon chain.
 mod simple_sync;
 
-pub use simple_sync::SimpleSync;
+pub use simple_sync::{PeerHeadSlots, SimpleSync};
 
 /// Currently implemented sync methods.
 pub enum SyncMethod {
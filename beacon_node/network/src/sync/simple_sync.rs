@@ -4,8 +4,10 @@ use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
 use eth2_libp2p::rpc::methods::*;
 use eth2_libp2p::rpc::{RPCEvent, RPCRequest, RPCResponse, RequestId};
 use eth2_libp2p::PeerId;
+use parking_lot::RwLock;
 use slog::{debug, info, o, trace, warn};
 use ssz::Encode;
+use std::collections::HashMap;
 use std::ops::Sub;
 use std::sync::Arc;
 use store::Store;
@@ -21,6 +23,10 @@ const FULL_PEER_TOLERANCE: u64 = 10;
 const SHOULD_FORWARD_GOSSIP_BLOCK: bool = true;
 const SHOULD_NOT_FORWARD_GOSSIP_BLOCK: bool = false;
 
+/// The head slot most recently advertised by each connected peer, shared with the network service
+/// so it can be reported externally.
+pub type PeerHeadSlots = Arc<RwLock<HashMap<PeerId, Slot>>>;
+
 /// Keeps track of syncing information for known connected peers.
 #[derive(Clone, Copy, Debug)]
 pub struct PeerSyncInfo {
@@ -63,6 +69,8 @@ pub struct SimpleSync<T: BeaconChainTypes> {
     chain: Arc<BeaconChain<T>>,
     manager: ImportManager<T>,
     network: NetworkContext,
+    /// The head slot advertised by each peer in its latest `Hello`.
+    peer_head_slots: PeerHeadSlots,
     log: slog::Logger,
 }
 
@@ -71,6 +79,7 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
     pub fn new(
         beacon_chain: Arc<BeaconChain<T>>,
         network_send: mpsc::UnboundedSender<NetworkMessage>,
+        peer_head_slots: PeerHeadSlots,
         log: &slog::Logger,
     ) -> Self {
         let sync_logger = log.new(o!("Service"=> "Sync"));
@@ -79,6 +88,7 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
             chain: beacon_chain.clone(),
             manager: ImportManager::new(beacon_chain, log),
             network: NetworkContext::new(network_send, log.clone()),
+            peer_head_slots,
             log: sync_logger,
         }
     }
@@ -87,6 +97,7 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
     ///
     /// Removes the peer from the manager.
     pub fn on_disconnect(&mut self, peer_id: PeerId) {
+        self.peer_head_slots.write().remove(&peer_id);
         self.manager.peer_disconnect(&peer_id);
     }
 
@@ -134,6 +145,10 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
         let remote = PeerSyncInfo::from(hello);
         let local = PeerSyncInfo::from(&self.chain);
 
+        self.peer_head_slots
+            .write()
+            .insert(peer_id.clone(), remote.head_slot);
+
         let start_slot = |epoch: Epoch| epoch.start_slot(T::EthSpec::slots_per_epoch());
 
         if local.fork_version != remote.fork_version {
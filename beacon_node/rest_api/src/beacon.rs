@@ -33,6 +33,47 @@ pub fn get_head<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult
     Ok(success_response(Body::from(json)))
 }
 
+#[derive(Serialize)]
+pub struct ChainLagResponse {
+    /// The slot of the head block.
+    pub head_slot: u64,
+    /// The slot of the wall clock, from the genesis time and the slot duration.
+    pub current_slot: u64,
+    pub slots_behind: u64,
+    /// The number of whole epochs in `slots_behind`.
+    pub epochs_behind: u64,
+}
+
+/// HTTP handler to return how far the head block is behind the wall clock slot.
+///
+/// Returns `503 Service Unavailable` before genesis, when there is no wall clock slot.
+pub fn get_chain_lag<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult {
+    let beacon_chain = req
+        .extensions()
+        .get::<Arc<BeaconChain<T>>>()
+        .ok_or_else(|| ApiError::ServerError("Beacon chain extension missing".to_string()))?;
+
+    let current_slot = beacon_chain
+        .read_slot_clock()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Genesis has not yet occurred".to_string()))?
+        .as_u64();
+    let head_slot = beacon_chain.head().beacon_block.slot.as_u64();
+    let slots_behind = current_slot.saturating_sub(head_slot);
+
+    let lag = ChainLagResponse {
+        head_slot,
+        current_slot,
+        slots_behind,
+        epochs_behind: slots_behind / T::EthSpec::slots_per_epoch(),
+    };
+
+    let json: String = serde_json::to_string(&lag).map_err(|e| {
+        ApiError::ServerError(format!("Unable to serialize ChainLagResponse: {:?}", e))
+    })?;
+
+    Ok(success_response(Body::from(json)))
+}
+
 #[derive(Serialize)]
 #[serde(bound = "T: EthSpec")]
 pub struct BlockResponse<T: EthSpec> {
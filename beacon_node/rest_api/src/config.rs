@@ -11,6 +11,83 @@ pub struct Config {
     pub listen_address: Ipv4Addr,
     /// The port the REST API HTTP server will listen on.
     pub port: u16,
+    /// The number of seconds a request may take before a `504 Gateway Timeout` is returned. The
+    /// handler keeps running after the timeout, only its response is discarded.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Reject every request which is not a `GET` with a `403 Forbidden`.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Listeners in addition to the one at `listen_address` and `port`, e.g., a read-only
+    /// listener on a public interface.
+    #[serde(default)]
+    pub extra_listeners: Vec<ListenerConfig>,
+}
+
+/// An address and port on which the REST API HTTP server listens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    pub listen_address: Ipv4Addr,
+    pub port: u16,
+    /// Reject every request received on this listener which is not a `GET`.
+    pub read_only: bool,
+}
+
+impl ListenerConfig {
+    /// Parses an `address:port:mode` triple, where `mode` is `full` or `read-only`.
+    pub fn from_cli_str(listener: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = listener.split(':').collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "Invalid REST listener {:?}, expected address:port:mode",
+                listener
+            ));
+        }
+
+        let listen_address = parts[0].parse::<Ipv4Addr>().map_err(|_| {
+            format!(
+                "Invalid REST listener {:?}, {} is not a valid IPv4 address",
+                listener, parts[0]
+            )
+        })?;
+        let port = match parts[1].parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => {
+                return Err(format!(
+                    "Invalid REST listener {:?}, {} is not a valid non-zero port",
+                    listener, parts[1]
+                ))
+            }
+        };
+        let read_only = match parts[2] {
+            "full" => false,
+            "read-only" => true,
+            mode => {
+                return Err(format!(
+                    "Invalid REST listener {:?}, mode {} is not one of full or read-only",
+                    listener, mode
+                ))
+            }
+        };
+
+        Ok(Self {
+            listen_address,
+            port,
+            read_only,
+        })
+    }
+
+    /// Returns `true` if `self` and `other` would bind the same endpoint.
+    fn overlaps(&self, other: &Self) -> bool {
+        self.port == other.port
+            && (self.listen_address == other.listen_address
+                || self.listen_address.is_unspecified()
+                || other.listen_address.is_unspecified())
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    60
 }
 
 impl Default for Config {
@@ -19,28 +96,111 @@ impl Default for Config {
             enabled: true, // rest_api enabled by default
             listen_address: Ipv4Addr::new(127, 0, 0, 1),
             port: 5052,
+            timeout_secs: default_timeout_secs(),
+            read_only: false,
+            extra_listeners: vec![],
         }
     }
 }
 
 impl Config {
-    pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), &'static str> {
+    /// Returns every listener: the one at `listen_address` and `port` followed by
+    /// `extra_listeners`.
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        let primary = ListenerConfig {
+            listen_address: self.listen_address,
+            port: self.port,
+            read_only: self.read_only,
+        };
+
+        std::iter::once(primary)
+            .chain(self.extra_listeners.iter().cloned())
+            .collect()
+    }
+
+    /// Returns an error if two listeners would bind the same endpoint.
+    pub fn validate_listeners(&self) -> Result<(), String> {
+        let listeners = self.listeners();
+        for (i, listener) in listeners.iter().enumerate() {
+            if let Some(other) = listeners[..i].iter().find(|other| other.overlaps(listener)) {
+                return Err(format!(
+                    "REST listeners {}:{} and {}:{} bind the same endpoint",
+                    other.listen_address, other.port, listener.listen_address, listener.port
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), String> {
         if args.is_present("api") {
             self.enabled = true;
         }
 
+        if args.is_present("rest-read-only") {
+            self.read_only = true;
+        }
+
         if let Some(rpc_address) = args.value_of("api-address") {
             self.listen_address = rpc_address
                 .parse::<Ipv4Addr>()
-                .map_err(|_| "api-address is not a valid IPv4 address.")?;
+                .map_err(|_| "api-address is not a valid IPv4 address.".to_string())?;
         }
 
         if let Some(rpc_port) = args.value_of("api-port") {
             self.port = rpc_port
                 .parse::<u16>()
-                .map_err(|_| "api-port is not a valid u16.")?;
+                .map_err(|_| "api-port is not a valid u16.".to_string())?;
+            if self.port == 0 {
+                return Err(
+                    "api-port cannot be 0, the node would listen on an unknown port.".to_string(),
+                );
+            }
         }
 
-        Ok(())
+        if let Some(listeners) = args.values_of("rest-listener") {
+            self.extra_listeners = listeners
+                .map(ListenerConfig::from_cli_str)
+                .collect::<Result<_, _>>()?;
+        }
+
+        self.validate_listeners()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listener_from_cli_str() {
+        assert_eq!(
+            ListenerConfig::from_cli_str("0.0.0.0:5053:read-only"),
+            Ok(ListenerConfig {
+                listen_address: Ipv4Addr::new(0, 0, 0, 0),
+                port: 5053,
+                read_only: true,
+            })
+        );
+        assert!(ListenerConfig::from_cli_str("127.0.0.1:5053").is_err());
+        assert!(ListenerConfig::from_cli_str("127.0.0.1:0:full").is_err());
+        assert!(ListenerConfig::from_cli_str("127.0.0.1:5053:admin").is_err());
+    }
+
+    #[test]
+    fn duplicate_listeners_are_rejected() {
+        let mut config = Config::default();
+        config.extra_listeners = vec![ListenerConfig::from_cli_str("10.0.0.1:5052:full").unwrap()];
+        assert!(config.validate_listeners().is_ok());
+
+        config.extra_listeners = vec![ListenerConfig::from_cli_str("0.0.0.0:5052:full").unwrap()];
+        assert!(config.validate_listeners().is_err());
+
+        config.extra_listeners = vec![
+            ListenerConfig::from_cli_str("10.0.0.1:5053:full").unwrap(),
+            ListenerConfig::from_cli_str("10.0.0.1:5053:read-only").unwrap(),
+        ];
+        assert!(config.validate_listeners().is_err());
     }
 }
@@ -1,6 +1,7 @@
 use crate::{ApiError, ApiResult};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use bls::PublicKey;
+use eth2_libp2p::PeerId;
 use hex;
 use hyper::{Body, Request, StatusCode};
 use serde::de::value::StringDeserializer;
@@ -53,6 +54,15 @@ pub fn parse_pubkey(string: &str) -> Result<PublicKey, ApiError> {
     }
 }
 
+/// Parse a base58-encoded `PeerId`, as given in the path of peer-scoped requests.
+///
+/// E.g., `"QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N"`
+pub fn parse_peer_id(string: &str) -> Result<PeerId, ApiError> {
+    string
+        .parse::<PeerId>()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid peer id {:?}: {:?}", string, e)))
+}
+
 /// Returns the root of the `BeaconBlock` in the canonical chain of `beacon_chain` at the given
 /// `slot`, if possible.
 ///
@@ -199,4 +209,13 @@ mod test {
         assert_eq!(parse_slot("10000000"), Ok(Slot::new(10_000_000)));
         assert!(parse_slot("cats").is_err());
     }
+
+    #[test]
+    fn parse_peer_id_works() {
+        assert!(parse_peer_id("QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N").is_ok());
+        match parse_peer_id("not-a-peer") {
+            Err(ApiError::BadRequest(desc)) => assert!(desc.contains("not-a-peer")),
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
 }
@@ -14,18 +14,26 @@ mod validator;
 
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use client_network::Service as NetworkService;
-use hyper::rt::Future;
-use hyper::service::service_fn_ok;
-use hyper::{Body, Method, Response, Server, StatusCode};
-use slog::{info, o, warn};
+use eth2_config::Eth2Config;
+use futures::sync::oneshot;
+use futures::{future, stream};
+use hyper::header::{HeaderValue, RETRY_AFTER};
+use hyper::rt::{Future, Stream};
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Serialize;
+use slog::{debug, info, o, warn};
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::runtime::TaskExecutor;
+use tokio::timer::Timeout;
 use url_query::UrlQuery;
 
 pub use beacon::{BlockResponse, HeadResponse, StateResponse};
-pub use config::Config as ApiConfig;
+pub use config::{Config as ApiConfig, ListenerConfig};
 
 #[derive(PartialEq, Debug)]
 pub enum ApiError {
@@ -33,10 +41,36 @@ pub enum ApiError {
     ServerError(String),
     NotImplemented(String),
     InvalidQueryParams(String),
+    /// The request is malformed, e.g., a path parameter cannot be parsed.
+    BadRequest(String),
     NotFound(String),
+    /// The request is not permitted, e.g., a mutating request to a read-only server.
+    Forbidden(String),
+    /// A required service has not yet been initialized. The client should retry later.
+    ServiceUnavailable(String),
+    /// The request did not complete within the configured timeout.
+    GatewayTimeout(String),
+    /// The request is rate limited. The client should retry later.
+    TooManyRequests(String),
+    /// The request body is larger than `MAX_REQUEST_BODY_BYTES`.
+    PayloadTooLarge(String),
     ImATeapot(String), // Just in case.
 }
 
+/// The number of seconds a client is asked to wait before retrying a `ServiceUnavailable` request.
+const RETRY_AFTER_SECONDS: u64 = 5;
+
+/// Arrays with more items than this are streamed by `json_array_response`, rather than serialized
+/// in full before responding.
+const STREAMED_ARRAY_THRESHOLD: usize = 1_000;
+
+/// The largest request body that is read, in bytes. Larger bodies are refused with `413`.
+const MAX_REQUEST_BODY_BYTES: usize = 4 * 1024;
+
+/// The response header carrying the id the request was logged with, so that a client can correlate
+/// a response (including an error response) with the server logs.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
 pub type ApiResult = Result<Response<Body>, ApiError>;
 
 impl Into<Response<Body>> for ApiError {
@@ -46,11 +80,21 @@ impl Into<Response<Body>> for ApiError {
             ApiError::ServerError(desc) => (StatusCode::INTERNAL_SERVER_ERROR, desc),
             ApiError::NotImplemented(desc) => (StatusCode::NOT_IMPLEMENTED, desc),
             ApiError::InvalidQueryParams(desc) => (StatusCode::BAD_REQUEST, desc),
+            ApiError::BadRequest(desc) => (StatusCode::BAD_REQUEST, desc),
             ApiError::NotFound(desc) => (StatusCode::NOT_FOUND, desc),
+            ApiError::Forbidden(desc) => (StatusCode::FORBIDDEN, desc),
+            ApiError::ServiceUnavailable(desc) => (StatusCode::SERVICE_UNAVAILABLE, desc),
+            ApiError::GatewayTimeout(desc) => (StatusCode::GATEWAY_TIMEOUT, desc),
+            ApiError::TooManyRequests(desc) => (StatusCode::TOO_MANY_REQUESTS, desc),
+            ApiError::PayloadTooLarge(desc) => (StatusCode::PAYLOAD_TOO_LARGE, desc),
             ApiError::ImATeapot(desc) => (StatusCode::IM_A_TEAPOT, desc),
         };
-        Response::builder()
-            .status(status_code.0)
+        let mut builder = Response::builder();
+        builder.status(status_code.0);
+        if status_code.0 == StatusCode::SERVICE_UNAVAILABLE {
+            builder.header(RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECONDS));
+        }
+        builder
             .body(Body::from(status_code.1))
             .expect("Response should always be created.")
     }
@@ -79,6 +123,7 @@ pub fn start_server<T: BeaconChainTypes>(
     executor: &TaskExecutor,
     beacon_chain: Arc<BeaconChain<T>>,
     network_service: Arc<NetworkService<T>>,
+    eth2_config: Arc<Eth2Config>,
     db_path: PathBuf,
     log: &slog::Logger,
 ) -> Result<exit_future::Signal, hyper::Error> {
@@ -87,160 +132,334 @@ pub fn start_server<T: BeaconChainTypes>(
     // build a channel to kill the HTTP server
     let (exit_signal, exit) = exit_future::signal();
 
-    let exit_log = log.clone();
-    let server_exit = exit.and_then(move |_| {
-        info!(exit_log, "API service shutdown");
-        Ok(())
-    });
-
     let db_path = DBPath(db_path);
+    let start_time = StartTime(Instant::now());
+    let timeout = Duration::from_secs(config.timeout_secs);
+    // Shared by all listeners so that request ids are unique across them.
+    let request_ids = Arc::new(AtomicU64::new(0));
+
+    // Each listener is served independently, differing only in whether it is read-only.
+    for listener in config.listeners() {
+        let exit_log = log.clone();
+        let server_exit = exit.clone().and_then(move |_| {
+            info!(exit_log, "API service shutdown");
+            Ok(())
+        });
+
+        // Get the address to bind to
+        let bind_addr = (listener.listen_address, listener.port).into();
+        let read_only = listener.read_only;
+
+        // Clone our stateful objects, for use in service closure.
+        let server_log = log.clone();
+        let server_bc = beacon_chain.clone();
+        let server_db_path = db_path.clone();
+        let server_network = network_service.clone();
+        let server_eth2_config = eth2_config.clone();
+        let server_executor = executor.clone();
+        let server_request_ids = request_ids.clone();
+
+        let service = move || {
+            let log = server_log.clone();
+            let beacon_chain = server_bc.clone();
+            let db_path = server_db_path.clone();
+            let network_service = server_network.clone();
+            let eth2_config = server_eth2_config.clone();
+            let executor = server_executor.clone();
+            let request_ids = server_request_ids.clone();
+
+            // Create a handler for the router, inject our stateful objects into the request.
+            service_fn(move |req: Request<Body>| {
+                metrics::inc_counter(&metrics::REQUEST_COUNT);
+                let timer = metrics::start_timer(&metrics::REQUEST_RESPONSE_TIME);
+                let received = Instant::now();
+
+                let request_id = request_ids.fetch_add(1, Ordering::Relaxed);
+                let log = log.new(o!("request_id" => request_id));
+                let beacon_chain = beacon_chain.clone();
+                let db_path = db_path.clone();
+                let network_service = network_service.clone();
+                let eth2_config = eth2_config.clone();
+                let executor = executor.clone();
+
+                // Buffer the request body, if the route reads one, so that handlers may read it
+                // synchronously.
+                let (parts, body) = req.into_parts();
+                let method = parts.method.clone();
+                let path = parts.uri.path().to_string();
+                let body = if !read_only && reads_body(&method, &path) {
+                    future::Either::A(read_body(body))
+                } else {
+                    future::Either::B(future::ok(Vec::new()))
+                };
+                let handler_log = log.clone();
+                body.then(move |body| {
+                    let body = match body {
+                        Ok(body) => body,
+                        Err(e) => {
+                            return future::Either::A(future::ok::<_, hyper::Error>(e.into()))
+                        }
+                    };
+
+                    let mut req = Request::from_parts(parts, Body::empty());
+                    req.extensions_mut().insert::<RequestBody>(RequestBody(body));
+                    req.extensions_mut().insert::<slog::Logger>(handler_log.clone());
+                    req.extensions_mut()
+                        .insert::<Arc<BeaconChain<T>>>(beacon_chain.clone());
+                    req.extensions_mut().insert::<DBPath>(db_path.clone());
+                    req.extensions_mut().insert::<StartTime>(start_time);
+                    req.extensions_mut()
+                        .insert::<Arc<NetworkService<T>>>(network_service.clone());
+                    req.extensions_mut()
+                        .insert::<Arc<Eth2Config>>(eth2_config.clone());
+
+                    let (response_tx, response_rx) = oneshot::channel();
+
+                    // Handlers are synchronous, so they are run on the executor and the response
+                    // is awaited with a timeout. A handler cannot be interrupted, so after a
+                    // timeout it still runs to completion and its response is discarded.
+                    let route_log = handler_log;
+                    executor.spawn(future::lazy(move || {
+                        let _ = response_tx.send(route::<T>(req, read_only, &route_log));
+                        Ok(())
+                    }));
+
+                    future::Either::B(Timeout::new(response_rx, timeout).then(|result| {
+                        Ok::<Response<Body>, hyper::Error>(match result {
+                            Ok(response) => response,
+                            Err(e) if e.is_elapsed() => {
+                                ApiError::GatewayTimeout("Request timed out.".to_string()).into()
+                            }
+                            Err(e) => {
+                                ApiError::ServerError(format!("Request failed: {:?}", e)).into()
+                            }
+                        })
+                    }))
+                })
+                .map(move |mut response: Response<Body>| {
+                    metrics::stop_timer(timer);
 
-    // Get the address to bind to
-    let bind_addr = (config.listen_address, config.port).into();
-
-    // Clone our stateful objects, for use in service closure.
-    let server_log = log.clone();
-    let server_bc = beacon_chain.clone();
-
-    let service = move || {
-        let log = server_log.clone();
-        let beacon_chain = server_bc.clone();
-        let db_path = db_path.clone();
-        let network_service = network_service.clone();
-
-        // Create a simple handler for the router, inject our stateful objects into the request.
-        service_fn_ok(move |mut req| {
-            metrics::inc_counter(&metrics::REQUEST_COUNT);
-            let timer = metrics::start_timer(&metrics::REQUEST_RESPONSE_TIME);
-
-            req.extensions_mut().insert::<slog::Logger>(log.clone());
-            req.extensions_mut()
-                .insert::<Arc<BeaconChain<T>>>(beacon_chain.clone());
-            req.extensions_mut().insert::<DBPath>(db_path.clone());
-            req.extensions_mut()
-                .insert::<Arc<NetworkService<T>>>(network_service.clone());
-
-            let path = req.uri().path().to_string();
-
-            // Route the request to the correct handler.
-            let result = match (req.method(), path.as_ref()) {
-                // Methods for Beacon Node
-                //TODO: Remove?
-                //(&Method::GET, "/beacon/best_slot") => beacon::get_best_slot::<T>(req),
-                (&Method::GET, "/beacon/head") => beacon::get_head::<T>(req),
-                (&Method::GET, "/beacon/block") => beacon::get_block::<T>(req),
-                (&Method::GET, "/beacon/blocks") => helpers::implementation_pending_response(req),
-                //TODO Is the below replaced by finalized_checkpoint?
-                (&Method::GET, "/beacon/chainhead") => {
-                    helpers::implementation_pending_response(req)
-                }
-                (&Method::GET, "/beacon/block_root") => beacon::get_block_root::<T>(req),
-                (&Method::GET, "/beacon/latest_finalized_checkpoint") => {
-                    beacon::get_latest_finalized_checkpoint::<T>(req)
-                }
-                (&Method::GET, "/beacon/state") => beacon::get_state::<T>(req),
-                (&Method::GET, "/beacon/state_root") => beacon::get_state_root::<T>(req),
-
-                //TODO: Add aggreggate/filtered state lookups here, e.g. /beacon/validators/balances
-
-                // Methods for Client
-                (&Method::GET, "/metrics") => metrics::get_prometheus::<T>(req),
-                (&Method::GET, "/network/enr") => network::get_enr::<T>(req),
-                (&Method::GET, "/network/peer_count") => network::get_peer_count::<T>(req),
-                (&Method::GET, "/network/peer_id") => network::get_peer_id::<T>(req),
-                (&Method::GET, "/network/peers") => network::get_peer_list::<T>(req),
-                (&Method::GET, "/network/listen_port") => network::get_listen_port::<T>(req),
-                (&Method::GET, "/network/listen_addresses") => {
-                    network::get_listen_addresses::<T>(req)
-                }
-                (&Method::GET, "/node/version") => node::get_version(req),
-                (&Method::GET, "/node/genesis_time") => node::get_genesis_time::<T>(req),
-                (&Method::GET, "/node/deposit_contract") => {
-                    helpers::implementation_pending_response(req)
-                }
-                (&Method::GET, "/node/syncing") => helpers::implementation_pending_response(req),
-                (&Method::GET, "/node/fork") => helpers::implementation_pending_response(req),
-
-                // Methods for Network
-                (&Method::GET, "/network/enr") => network::get_enr::<T>(req),
-                (&Method::GET, "/network/peer_count") => network::get_peer_count::<T>(req),
-                (&Method::GET, "/network/peer_id") => network::get_peer_id::<T>(req),
-                (&Method::GET, "/network/peers") => network::get_peer_list::<T>(req),
-                (&Method::GET, "/network/listen_addresses") => {
-                    network::get_listen_addresses::<T>(req)
-                }
-
-                // Methods for Validator
-                (&Method::GET, "/validator/duties") => validator::get_validator_duties::<T>(req),
-                (&Method::GET, "/validator/block") => helpers::implementation_pending_response(req),
-                (&Method::POST, "/validator/block") => {
-                    helpers::implementation_pending_response(req)
-                }
-                (&Method::GET, "/validator/attestation") => {
-                    helpers::implementation_pending_response(req)
-                }
-                (&Method::POST, "/validator/attestation") => {
-                    helpers::implementation_pending_response(req)
-                }
-
-                (&Method::GET, "/spec") => spec::get_spec::<T>(req),
-                (&Method::GET, "/spec/slots_per_epoch") => spec::get_slots_per_epoch::<T>(req),
-
-                _ => Err(ApiError::NotFound(
-                    "Request path and/or method not found.".to_owned(),
-                )),
-            };
-
-            let response = match result {
-                // Return the `hyper::Response`.
-                Ok(response) => {
-                    metrics::inc_counter(&metrics::SUCCESS_COUNT);
-                    slog::debug!(log, "Request successful: {:?}", path);
                     response
-                }
-                // Map the `ApiError` into `hyper::Response`.
-                Err(e) => {
-                    slog::debug!(log, "Request failure: {:?}", path);
-                    e.into()
-                }
-            };
+                        .headers_mut()
+                        .insert(REQUEST_ID_HEADER, HeaderValue::from(request_id));
+
+                    let duration = received.elapsed();
+                    debug!(
+                        log,
+                        "API request";
+                        "method" => format!("{}", method),
+                        "path" => &path,
+                        "status" => response.status().as_u16(),
+                        "duration_ms" => duration.as_secs() * 1_000 + u64::from(duration.subsec_millis()),
+                    );
 
-            metrics::stop_timer(timer);
+                    response
+                })
+            })
+        };
 
-            response
-        })
-    };
+        let log_clone = log.clone();
+        let server = Server::bind(&bind_addr)
+            .serve(service)
+            .with_graceful_shutdown(server_exit)
+            .map_err(move |e| {
+                warn!(
+                    log_clone,
+                    "API failed to start, Unable to bind"; "address" => format!("{:?}", e)
+                )
+            });
+
+        info!(
+            log,
+            "REST API started";
+            "address" => format!("{}", listener.listen_address),
+            "port" => listener.port,
+            "read_only" => read_only,
+        );
+
+        executor.spawn(server);
+    }
 
-    let log_clone = log.clone();
-    let server = Server::bind(&bind_addr)
-        .serve(service)
-        .with_graceful_shutdown(server_exit)
-        .map_err(move |e| {
-            warn!(
-                log_clone,
-                "API failed to start, Unable to bind"; "address" => format!("{:?}", e)
-            )
-        });
+    Ok(exit_signal)
+}
 
-    info!(
-        log,
-        "REST API started";
-        "address" => format!("{}", config.listen_address),
-        "port" => config.port,
-    );
+/// Returns `true` if the route of a `method` request to `path` reads the request body.
+///
+/// Other bodies are never read, so that they cannot be used to exhaust the memory of the node.
+fn reads_body(method: &Method, path: &str) -> bool {
+    *method == Method::POST && path.starts_with("/network/peers/") && path.ends_with("/ban")
+}
 
-    executor.spawn(server);
+/// Reads `body` into memory, failing with `PayloadTooLarge` once it exceeds
+/// `MAX_REQUEST_BODY_BYTES`.
+fn read_body(body: Body) -> impl Future<Item = Vec<u8>, Error = ApiError> {
+    body.map_err(|e| ApiError::BadRequest(format!("Unable to read request body: {:?}", e)))
+        .fold(Vec::new(), |mut bytes, chunk| {
+            if bytes.len() + chunk.len() > MAX_REQUEST_BODY_BYTES {
+                Err(ApiError::PayloadTooLarge(format!(
+                    "Request body exceeds {} bytes",
+                    MAX_REQUEST_BODY_BYTES
+                )))
+            } else {
+                bytes.extend_from_slice(&chunk);
+                Ok(bytes)
+            }
+        })
+}
 
-    Ok(exit_signal)
+/// Routes `req` to the appropriate handler, returning its response or error response.
+///
+/// If `read_only` is set, any request other than a `GET` is refused before reaching a handler.
+fn route<T: BeaconChainTypes>(
+    req: Request<Body>,
+    read_only: bool,
+    log: &slog::Logger,
+) -> Response<Body> {
+    let path = req.uri().path().to_string();
+
+    // Route the request to the correct handler.
+    let result = match (req.method(), path.as_ref()) {
+        (method, _) if read_only && *method != Method::GET => Err(ApiError::Forbidden(
+            "The API is read-only, only GET requests are permitted.".to_owned(),
+        )),
+
+        // Methods for Beacon Node
+        //TODO: Remove?
+        //(&Method::GET, "/beacon/best_slot") => beacon::get_best_slot::<T>(req),
+        (&Method::GET, "/beacon/head") => beacon::get_head::<T>(req),
+        (&Method::GET, "/beacon/chain_lag") => beacon::get_chain_lag::<T>(req),
+        (&Method::GET, "/beacon/block") => beacon::get_block::<T>(req),
+        (&Method::GET, "/beacon/blocks") => helpers::implementation_pending_response(req),
+        //TODO Is the below replaced by finalized_checkpoint?
+        (&Method::GET, "/beacon/chainhead") => helpers::implementation_pending_response(req),
+        (&Method::GET, "/beacon/block_root") => beacon::get_block_root::<T>(req),
+        (&Method::GET, "/beacon/latest_finalized_checkpoint") => {
+            beacon::get_latest_finalized_checkpoint::<T>(req)
+        }
+        (&Method::GET, "/beacon/state") => beacon::get_state::<T>(req),
+        (&Method::GET, "/beacon/state_root") => beacon::get_state_root::<T>(req),
+
+        //TODO: Add aggreggate/filtered state lookups here, e.g. /beacon/validators/balances
+
+        // Methods for Client
+        (&Method::GET, "/metrics") => metrics::get_prometheus::<T>(req),
+        (&Method::GET, "/network/enr") => network::get_enr::<T>(req),
+        (&Method::GET, "/network/peer_count") => network::get_peer_count::<T>(req),
+        (&Method::GET, "/network/peer_id") => network::get_peer_id::<T>(req),
+        (&Method::GET, "/network/peers") => network::get_peer_list::<T>(req),
+        (&Method::GET, "/network/listen_port") => network::get_listen_port::<T>(req),
+        (&Method::GET, "/network/listen_addresses") => network::get_listen_addresses::<T>(req),
+        (&Method::GET, "/network/peer_agents") => network::get_peer_agents::<T>(req),
+        (&Method::GET, "/network/peers_by_client") => network::get_peers_by_client::<T>(req),
+        (&Method::GET, "/network/peers_by_fork") => network::get_peers_by_fork::<T>(req),
+        (&Method::GET, "/network/peer_scores") => network::get_peer_scores::<T>(req),
+        (&Method::GET, "/network/banned_peers") => network::get_banned_peers::<T>(req),
+        (&Method::GET, "/network/peer_latency") => network::get_peer_latency::<T>(req),
+        (&Method::GET, "/network/stats") => network::get_network_stats::<T>(req),
+        (&Method::GET, "/network/config") => network::get_network_config::<T>(req),
+        (&Method::GET, "/network/peer_addr_info") => network::get_peer_addr_info::<T>(req),
+        (&Method::GET, "/network/gossip_topics") => network::get_gossip_topics::<T>(req),
+        (&Method::GET, "/network/gossip_info") => network::get_gossip_info::<T>(req),
+        (&Method::GET, "/network/gossip_mesh") => network::get_gossip_mesh::<T>(req),
+        (&Method::GET, "/network/subnet_subscriptions") => {
+            network::get_subnet_subscriptions::<T>(req)
+        }
+        (&Method::GET, "/network/sync") => network::get_sync_status::<T>(req),
+        (&Method::GET, "/network/node_version") => network::get_node_version::<T>(req),
+        (&Method::GET, "/network/bootnode_status") => network::get_bootnode_status::<T>(req),
+        (&Method::POST, "/network/discover") => network::post_discover::<T>(req),
+        (&Method::GET, p) if p.starts_with("/network/peers/") => network::get_peer_info::<T>(req),
+        (&Method::POST, p) if p.starts_with("/network/peers/") && p.ends_with("/ban") => {
+            network::post_ban_peer::<T>(req)
+        }
+        (&Method::POST, p) if p.starts_with("/network/peers/") && p.ends_with("/unban") => {
+            network::post_unban_peer::<T>(req)
+        }
+        (&Method::GET, "/node/version") => node::get_version(req),
+        (&Method::GET, "/node/uptime") => node::get_uptime(req),
+        (&Method::GET, "/node/health") => node::get_health::<T>(req),
+        (&Method::GET, "/node/genesis_time") => node::get_genesis_time::<T>(req),
+        (&Method::GET, "/node/genesis_info") => node::get_genesis_info::<T>(req),
+        (&Method::GET, "/node/deposit_contract") => helpers::implementation_pending_response(req),
+        (&Method::GET, "/node/syncing") => helpers::implementation_pending_response(req),
+        (&Method::GET, "/node/fork") => helpers::implementation_pending_response(req),
+
+        // Methods for Network
+        (&Method::GET, "/network/enr") => network::get_enr::<T>(req),
+        (&Method::GET, "/network/peer_count") => network::get_peer_count::<T>(req),
+        (&Method::GET, "/network/peer_id") => network::get_peer_id::<T>(req),
+        (&Method::GET, "/network/peers") => network::get_peer_list::<T>(req),
+        (&Method::GET, "/network/listen_addresses") => network::get_listen_addresses::<T>(req),
+
+        // Methods for Validator
+        (&Method::GET, "/validator/duties") => validator::get_validator_duties::<T>(req),
+        (&Method::GET, "/validator/block") => helpers::implementation_pending_response(req),
+        (&Method::POST, "/validator/block") => helpers::implementation_pending_response(req),
+        (&Method::GET, "/validator/attestation") => helpers::implementation_pending_response(req),
+        (&Method::POST, "/validator/attestation") => helpers::implementation_pending_response(req),
+
+        (&Method::GET, "/spec") => spec::get_spec::<T>(req),
+        (&Method::GET, "/spec/slots_per_epoch") => spec::get_slots_per_epoch::<T>(req),
+        (&Method::GET, "/spec/eth2_config") => spec::get_eth2_config::<T>(req),
+
+        _ => Err(ApiError::NotFound(
+            "Request path and/or method not found.".to_owned(),
+        )),
+    };
+
+    match result {
+        // Return the `hyper::Response`.
+        Ok(response) => {
+            metrics::inc_counter(&metrics::SUCCESS_COUNT);
+            response
+        }
+        // Map the `ApiError` into `hyper::Response`.
+        Err(e) => {
+            debug!(log, "Request failure"; "path" => &path, "error" => format!("{:?}", e));
+            e.into()
+        }
+    }
 }
 
+/// Returns a `200 OK` response with the given `body`.
 fn success_response(body: Body) -> Response<Body> {
+    success_response_with_status(StatusCode::OK, body)
+}
+
+/// Returns a successful response with the given `status`, e.g., `201 Created` or `202 Accepted`.
+fn success_response_with_status(status: StatusCode, body: Body) -> Response<Body> {
     Response::builder()
-        .status(StatusCode::OK)
+        .status(status)
         .body(body)
         .expect("We should always be able to make response from the success body.")
 }
 
+/// Returns a `200 OK` response with `items` as a JSON array.
+///
+/// Large arrays are serialized one item at a time as hyper polls the body, so the response starts
+/// without waiting for the whole JSON string to be built. `items` is still collected up front and
+/// held until the body has been sent, so memory use grows with the length of the array.
+fn json_array_response<T>(items: Vec<T>) -> ApiResult
+where
+    T: Serialize + Send + 'static,
+{
+    if items.len() <= STREAMED_ARRAY_THRESHOLD {
+        return serde_json::to_string(&items)
+            .map(|json| success_response(Body::from(json)))
+            .map_err(|e| ApiError::ServerError(format!("Unable to serialize array: {:?}", e)));
+    }
+
+    let elements = items.into_iter().enumerate().map(|(i, item)| {
+        let mut chunk = if i == 0 { vec![] } else { vec![b','] };
+        serde_json::to_writer(&mut chunk, &item).map(|()| chunk)
+    });
+    let body = stream::once(Ok(b"[".to_vec()))
+        .chain(stream::iter_result(elements))
+        .chain(stream::once(Ok(b"]".to_vec())));
+
+    Ok(success_response(Body::wrap_stream(body)))
+}
+
 #[derive(Clone)]
 pub struct DBPath(PathBuf);
 
@@ -251,3 +470,56 @@ impl Deref for DBPath {
         &self.0
     }
 }
+
+/// The time at which the REST API was started, inserted into the request extensions by the router.
+#[derive(Clone, Copy)]
+pub struct StartTime(pub Instant);
+
+/// The buffered body of a request, inserted into the request extensions by the router.
+#[derive(Clone, Default)]
+pub struct RequestBody(pub Vec<u8>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_string(response: Response<Body>) -> String {
+        let body = response
+            .into_body()
+            .concat2()
+            .wait()
+            .expect("body should be readable");
+        String::from_utf8(body.to_vec()).expect("body should be UTF-8")
+    }
+
+    #[test]
+    fn json_array_response_streams_large_arrays() {
+        for len in &[
+            0,
+            1,
+            STREAMED_ARRAY_THRESHOLD + 1,
+            STREAMED_ARRAY_THRESHOLD * 2,
+        ] {
+            let items: Vec<String> = (0..*len).map(|i| i.to_string()).collect();
+            let expected = serde_json::to_string(&items).unwrap();
+
+            let response = json_array_response(items).expect("should serialize");
+            assert_eq!(body_string(response), expected);
+        }
+    }
+
+    #[test]
+    fn read_body_is_capped() {
+        let body = vec![1; MAX_REQUEST_BODY_BYTES];
+        assert_eq!(read_body(Body::from(body.clone())).wait(), Ok(body));
+
+        let result = read_body(Body::from(vec![1; MAX_REQUEST_BODY_BYTES + 1])).wait();
+        assert_eq!(
+            result,
+            Err(ApiError::PayloadTooLarge(format!(
+                "Request body exceeds {} bytes",
+                MAX_REQUEST_BODY_BYTES
+            )))
+        );
+    }
+}
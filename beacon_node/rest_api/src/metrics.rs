@@ -1,4 +1,5 @@
-use crate::{success_response, ApiError, ApiResult, DBPath};
+use crate::network::get_network_service;
+use crate::{success_response, ApiError, ApiResult, DBPath, NetworkService};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use hyper::{Body, Request};
 use prometheus::{Encoder, TextEncoder};
@@ -19,6 +20,30 @@ lazy_static! {
         "http_server_success_total",
         "Total count of HTTP 200 responses sent"
     );
+    pub static ref CONNECTED_PEERS: Result<IntGauge> = try_create_int_gauge(
+        "lighthouse_connected_peers",
+        "Count of libp2p peers currently connected"
+    );
+    pub static ref INBOUND_PEERS: Result<IntGauge> = try_create_int_gauge(
+        "lighthouse_inbound_peers",
+        "Count of currently connected libp2p peers that dialed this node"
+    );
+    pub static ref OUTBOUND_PEERS: Result<IntGauge> = try_create_int_gauge(
+        "lighthouse_outbound_peers",
+        "Count of currently connected libp2p peers that this node dialed"
+    );
+    pub static ref LIBP2P_LISTEN_PORT: Result<IntGauge> = try_create_int_gauge(
+        "lighthouse_libp2p_listen_port",
+        "The TCP port libp2p is configured to listen on"
+    );
+}
+
+/// Updates the network metrics which are only read at the time of a scrape.
+fn scrape_network_metrics<T: BeaconChainTypes>(network: &NetworkService<T>) {
+    set_gauge(&CONNECTED_PEERS, network.connected_peers() as i64);
+    set_gauge(&INBOUND_PEERS, network.connected_inbound_peers() as i64);
+    set_gauge(&OUTBOUND_PEERS, network.connected_outbound_peers() as i64);
+    set_gauge(&LIBP2P_LISTEN_PORT, i64::from(network.listen_port()));
 }
 
 /// Returns the full set of Prometheus metrics for the Beacon Node application.
@@ -38,6 +63,7 @@ pub fn get_prometheus<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiR
         .extensions()
         .get::<DBPath>()
         .ok_or_else(|| ApiError::ServerError("DBPath extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     // There are two categories of metrics:
     //
@@ -58,6 +84,7 @@ pub fn get_prometheus<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiR
     slot_clock::scrape_for_metrics::<T::EthSpec, T::SlotClock>(&beacon_chain.slot_clock);
     store::scrape_for_metrics(&db_path);
     beacon_chain::scrape_for_metrics(&beacon_chain);
+    scrape_network_metrics(&network);
 
     encoder
         .encode(&lighthouse_metrics::gather(), &mut buffer)
@@ -1,17 +1,37 @@
-use crate::{success_response, ApiError, ApiResult, NetworkService};
-use beacon_chain::BeaconChainTypes;
-use eth2_libp2p::{Enr, Multiaddr, PeerId};
-use hyper::{Body, Request};
+use crate::helpers::parse_peer_id;
+use crate::{
+    json_array_response, success_response, success_response_with_status, ApiError, ApiResult,
+    NetworkService, RequestBody, UrlQuery,
+};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::multiaddr::Protocol;
+use eth2_libp2p::{Enr, Multiaddr, PeerId, FORK_DIGEST_LEN, MAX_BAN_DURATION, PROTOCOL_VERSION};
+use hyper::{Body, Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Returns the `NetworkService` attached to `req`.
+///
+/// The service is absent until the network has been initialized, which is reported as a
+/// `ServiceUnavailable` error so that clients know to retry rather than treating it as a crash.
+pub(crate) fn get_network_service<T: BeaconChainTypes>(
+    req: &Request<Body>,
+) -> Result<&Arc<NetworkService<T>>, ApiError> {
+    req.extensions()
+        .get::<Arc<NetworkService<T>>>()
+        .ok_or_else(|| {
+            ApiError::ServiceUnavailable("NetworkService is not yet initialized".to_string())
+        })
+}
 
 /// HTTP handle to return the list of libp2p multiaddr the client is listening on.
 ///
 /// Returns a list of `Multiaddr`, serialized according to their `serde` impl.
 pub fn get_listen_addresses<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     let multiaddresses: Vec<Multiaddr> = network.listen_multiaddrs();
 
@@ -25,10 +45,7 @@ pub fn get_listen_addresses<T: BeaconChainTypes>(req: Request<Body>) -> ApiResul
 ///
 /// Returns a list of `Multiaddr`, serialized according to their `serde` impl.
 pub fn get_listen_port<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     Ok(success_response(Body::from(
         serde_json::to_string(&network.listen_port())
@@ -40,10 +57,7 @@ pub fn get_listen_port<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
 ///
 /// ENR is encoded as base64 string.
 pub fn get_enr<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     let enr: Enr = network.local_enr();
 
@@ -57,10 +71,7 @@ pub fn get_enr<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
 ///
 /// PeerId is encoded as base58 string.
 pub fn get_peer_id<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     let peer_id: PeerId = network.local_peer_id();
 
@@ -72,10 +83,7 @@ pub fn get_peer_id<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
 
 /// HTTP handle to return the number of peers connected in the client's libp2p service.
 pub fn get_peer_count<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     let connected_peers: usize = network.connected_peers();
 
@@ -89,10 +97,7 @@ pub fn get_peer_count<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
 ///
 /// Peers are presented as a list of `PeerId::to_string()`.
 pub fn get_peer_list<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
-    let network = req
-        .extensions()
-        .get::<Arc<NetworkService<T>>>()
-        .ok_or_else(|| ApiError::ServerError("NetworkService extension missing".to_string()))?;
+    let network = get_network_service::<T>(&req)?;
 
     let connected_peers: Vec<String> = network
         .connected_peer_set()
@@ -100,9 +105,841 @@ pub fn get_peer_list<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
         .map(PeerId::to_string)
         .collect();
 
+    json_array_response(connected_peers)
+}
+
+/// HTTP handle to return the `NetworkConfig` the client's network service is running with.
+///
+/// This may differ from the config file in the datadir, e.g., if `--zero-ports` was used.
+pub fn get_network_config<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(network.config()).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize NetworkConfig: {:?}", e))
+        })?,
+    )))
+}
+
+/// HTTP handle to return the list of gossipsub topics the client's libp2p service is subscribed
+/// to.
+pub fn get_gossip_topics<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let topics: Vec<String> = network.gossip_topics();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&topics)
+            .map_err(|e| ApiError::ServerError(format!("Unable to serialize topics: {:?}", e)))?,
+    )))
+}
+
+/// HTTP handle to return, for each gossipsub topic the client is subscribed to, the connected
+/// peers that are subscribed to it too.
+pub fn get_gossip_mesh<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let mesh: HashMap<String, Vec<String>> = network
+        .gossip_mesh()
+        .into_iter()
+        .map(|(topic, peers)| {
+            let peers = peers.iter().map(PeerId::to_base58).collect();
+            (topic, peers)
+        })
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&mesh).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize gossip mesh: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct SubnetSubscription {
+    pub subnet_id: u64,
+    /// The slot after which the subscription lapses, or `None` if it does not, e.g., with
+    /// `--subscribe-all-subnets`.
+    pub expiry_slot: Option<u64>,
+}
+
+/// HTTP handle to return the attestation subnets the client's libp2p service is subscribed to.
+pub fn get_subnet_subscriptions<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    // Subnet topics are only subscribed at startup, so no subscription expires.
+    let subscriptions: Vec<SubnetSubscription> = network
+        .subnet_subscriptions()
+        .into_iter()
+        .map(|subnet_id| SubnetSubscription {
+            subnet_id,
+            expiry_slot: None,
+        })
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&subscriptions).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize subnet subscriptions: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct GossipInfo {
+    pub topics: Vec<String>,
+    /// gossipsub's `D`.
+    pub mesh_n: usize,
+    /// gossipsub's `D_low`.
+    pub mesh_n_low: usize,
+    /// gossipsub's `D_high`.
+    pub mesh_n_high: usize,
+    pub heartbeat_interval_ms: u64,
+}
+
+/// HTTP handle to return the subscribed gossipsub topics and the effective mesh parameters.
+pub fn get_gossip_info<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let gossip = network.gossip_config();
+
+    let info = GossipInfo {
+        topics: network.gossip_topics(),
+        mesh_n: gossip.mesh_n,
+        mesh_n_low: gossip.mesh_n_low,
+        mesh_n_high: gossip.mesh_n_high,
+        heartbeat_interval_ms: gossip.heartbeat_interval_ms,
+    };
+
     Ok(success_response(Body::from(
-        serde_json::to_string(&connected_peers).map_err(|e| {
-            ApiError::ServerError(format!("Unable to serialize Vec<PeerId>: {:?}", e))
+        serde_json::to_string(&info).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize GossipInfo: {:?}", e))
         })?,
     )))
 }
+
+/// HTTP handle to return the base64-encoded ENRs of recently discovered peers whose `eth2` field
+/// has the fork digest given by the `fork_digest` query parameter, e.g., `?fork_digest=0x01020304`.
+pub fn get_peers_by_fork<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let fork_digest = parse_fork_digest(&UrlQuery::from_request(&req)?.only_one("fork_digest")?)?;
+
+    let enrs: Vec<String> = network
+        .enrs_with_fork_digest(fork_digest)
+        .iter()
+        .map(Enr::to_base64)
+        .collect();
+
+    json_array_response(enrs)
+}
+
+/// Parses a `0x`-prefixed, 4 byte fork digest.
+fn parse_fork_digest(string: &str) -> Result<[u8; FORK_DIGEST_LEN], ApiError> {
+    if !string.starts_with("0x") {
+        return Err(ApiError::InvalidQueryParams(
+            "Fork digest must have a '0x' prefix".to_string(),
+        ));
+    }
+    let bytes = hex::decode(&string[2..])
+        .map_err(|e| ApiError::InvalidQueryParams(format!("Invalid fork digest: {:?}", e)))?;
+    if bytes.len() != FORK_DIGEST_LEN {
+        return Err(ApiError::InvalidQueryParams(format!(
+            "Fork digest must be {} bytes, got {} bytes",
+            FORK_DIGEST_LEN,
+            bytes.len()
+        )));
+    }
+
+    let mut fork_digest = [0; FORK_DIGEST_LEN];
+    fork_digest.copy_from_slice(&bytes);
+    Ok(fork_digest)
+}
+
+/// HTTP handle to return the agent version of each peer connected to the client's libp2p service.
+///
+/// Returns a map of `PeerId::to_string()` to agent version. Peers that have not identified
+/// themselves map to `null`.
+pub fn get_peer_agents<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let agents: HashMap<String, Option<String>> = network
+        .peer_agents()
+        .into_iter()
+        .map(|(peer_id, agent)| (peer_id.to_string(), agent))
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&agents).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize peer agents: {:?}", e))
+        })?,
+    )))
+}
+
+/// Client families recognised by `client_family`, with the agent string prefix (in lower case)
+/// identifying each.
+const CLIENT_FAMILIES: &[(&str, &str)] = &[
+    ("lighthouse", "Lighthouse"),
+    ("prysm", "Prysm"),
+    ("teku", "Teku"),
+    ("artemis", "Teku"),
+    ("nimbus", "Nimbus"),
+    ("lodestar", "Lodestar"),
+    ("js-libp2p", "Lodestar"),
+    ("harmony", "Harmony"),
+    ("trinity", "Trinity"),
+];
+
+/// Returns the name of the client family of a peer from its identify agent string, e.g.,
+/// `Lighthouse` for `Lighthouse/v0.1.0-unstable/x86_64-linux`, or `unknown`.
+fn client_family(agent: Option<&str>) -> &'static str {
+    let agent = agent
+        .map(|agent| agent.trim().to_lowercase())
+        .unwrap_or_default();
+    CLIENT_FAMILIES
+        .iter()
+        .find(|(prefix, _)| agent.starts_with(prefix))
+        .map_or("unknown", |(_, family)| family)
+}
+
+/// HTTP handle to return the number of connected peers running each client, for measuring
+/// client diversity.
+///
+/// Returns a map of client family (e.g. `Lighthouse`) to peer count. Peers which have not
+/// identified themselves or run an unrecognised client are counted as `unknown`.
+pub fn get_peers_by_client<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for agent in network.peer_agents().values() {
+        *counts
+            .entry(client_family(agent.as_ref().map(String::as_str)))
+            .or_insert(0) += 1;
+    }
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&counts).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize client counts: {:?}", e))
+        })?,
+    )))
+}
+
+/// HTTP handle to return the round-trip time of the last ping to each connected peer.
+///
+/// Returns a map of `PeerId::to_string()` to milliseconds, or `null` if the peer has not (yet)
+/// been pinged.
+pub fn get_peer_latency<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let latencies: HashMap<String, Option<u64>> = network
+        .peer_latencies()
+        .into_iter()
+        .map(|(peer_id, rtt)| {
+            let millis = rtt.map(|rtt| rtt.as_secs() * 1_000 + u64::from(rtt.subsec_millis()));
+            (peer_id.to_string(), millis)
+        })
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&latencies).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize peer latencies: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct NetworkStats {
+    pub connected_peers: usize,
+    pub target_peers: usize,
+    /// `None` if no peer has connected since startup.
+    pub seconds_since_last_peer_connected: Option<u64>,
+    /// `None` if no peer has disconnected since startup.
+    pub seconds_since_last_peer_disconnected: Option<u64>,
+    pub total_connections_since_startup: u64,
+    pub dials_in_flight: usize,
+    pub queued_dials: usize,
+}
+
+/// HTTP handle to return counters describing how the node has gained and lost peers, for
+/// detecting a stalled or isolated node.
+pub fn get_network_stats<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let stats = network.connection_stats();
+
+    let stats = NetworkStats {
+        connected_peers: stats.connected_peers,
+        target_peers: stats.target_peers,
+        seconds_since_last_peer_connected: stats.since_last_peer_connected.map(|d| d.as_secs()),
+        seconds_since_last_peer_disconnected: stats
+            .since_last_peer_disconnected
+            .map(|d| d.as_secs()),
+        total_connections_since_startup: stats.total_connections,
+        dials_in_flight: stats.dials_in_flight,
+        queued_dials: stats.queued_dials,
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&stats).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize network stats: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct PeerScoreResponse {
+    pub score: i32,
+    /// One of `Healthy`, `Disconnecting` or `Banned`.
+    pub state: String,
+    /// The unix time (in seconds) at which the peer's ban expires, if it is banned.
+    pub ban_expiry: Option<u64>,
+}
+
+/// HTTP handle to return the score and state of each connected or banned peer.
+///
+/// Returns a map of `PeerId::to_string()` to `PeerScoreResponse`.
+pub fn get_peer_scores<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let scores: HashMap<String, PeerScoreResponse> = network
+        .peer_scores()
+        .into_iter()
+        .map(|(peer_id, score)| {
+            let response = PeerScoreResponse {
+                score: score.score,
+                state: format!("{:?}", score.state),
+                ban_expiry: score.ban_expiry.map(unix_seconds),
+            };
+            (peer_id.to_string(), response)
+        })
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&scores).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize peer scores: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct BannedPeer {
+    pub peer_id: String,
+    /// The unix time (in seconds) at which the ban is lifted.
+    pub banned_until: Option<u64>,
+    pub reason: Option<String>,
+}
+
+/// HTTP handle to return the peers which are currently banned.
+///
+/// Returns a list of `BannedPeer`, ordered by `banned_until`.
+pub fn get_banned_peers<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let mut banned: Vec<BannedPeer> = network
+        .banned_peers()
+        .into_iter()
+        .map(|(peer_id, score)| BannedPeer {
+            peer_id: peer_id.to_string(),
+            banned_until: score.ban_expiry.map(unix_seconds),
+            reason: score.ban_reason,
+        })
+        .collect();
+    banned.sort_by_key(|peer| peer.banned_until);
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&banned).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize banned peers: {:?}", e))
+        })?,
+    )))
+}
+
+/// The body of a request to ban a peer. An empty body bans for the default duration.
+#[derive(Deserialize, Default)]
+pub struct BanRequest {
+    pub duration_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct BanResponse {
+    /// The unix time (in seconds) at which the ban expires.
+    pub ban_expiry: u64,
+}
+
+/// Parses the `PeerId` from a path of the form `/network/peers/{peer_id}/{action}`.
+fn peer_id_from_path(path: &str) -> Result<PeerId, ApiError> {
+    let peer_id_str = path
+        .trim_start_matches("/network/peers/")
+        .split('/')
+        .next()
+        .unwrap_or("");
+    parse_peer_id(peer_id_str)
+}
+
+#[derive(Serialize)]
+pub struct DialAttemptResponse {
+    /// The unix time (in seconds) of the attempt.
+    pub time: u64,
+    /// One of `connected`, `timeout`, `refused` or `protocol error: {reason}`.
+    pub outcome: String,
+}
+
+#[derive(Serialize)]
+pub struct PeerInfoResponse {
+    pub peer_id: String,
+    pub connected: bool,
+    /// The remote address of the connection to the peer, if connected.
+    pub address: Option<String>,
+    pub agent_version: Option<String>,
+    /// The protocols the peer advertised via identify, empty if it has not identified itself.
+    pub supported_protocols: Vec<String>,
+    pub score: Option<i32>,
+    /// The unix time (in seconds) at which the peer's ban expires, if it is banned.
+    pub ban_expiry: Option<u64>,
+    /// The most recent attempts to dial the peer, oldest first.
+    pub dial_history: Vec<DialAttemptResponse>,
+}
+
+/// HTTP handle to return what is known about a single peer, including recent dial attempts.
+///
+/// Handles `GET /network/peers/{peer_id}`. Returns `404` if nothing is known about the peer.
+pub fn get_peer_info<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let peer_id = peer_id_from_path(req.uri().path())?;
+
+    let score = network.peer_scores().remove(&peer_id);
+    let dial_history: Vec<DialAttemptResponse> = network
+        .dial_history(&peer_id)
+        .into_iter()
+        .map(|attempt| DialAttemptResponse {
+            time: unix_seconds(attempt.time),
+            outcome: attempt.outcome.to_string(),
+        })
+        .collect();
+    let address = network.peer_addresses().remove(&peer_id);
+
+    if score.is_none() && dial_history.is_empty() && address.is_none() {
+        return Err(ApiError::NotFound(format!("Unknown peer: {}", peer_id)));
+    }
+
+    let response = PeerInfoResponse {
+        peer_id: peer_id.to_string(),
+        connected: network.connected_peer_set().contains(&peer_id),
+        address: address.map(|address| address.to_string()),
+        agent_version: network
+            .peer_agents()
+            .remove(&peer_id)
+            .and_then(|agent| agent),
+        supported_protocols: network.peer_protocols(&peer_id),
+        score: score.as_ref().map(|score| score.score),
+        ban_expiry: score.and_then(|score| score.ban_expiry).map(unix_seconds),
+        dial_history,
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize PeerInfoResponse: {:?}", e))
+        })?,
+    )))
+}
+
+/// HTTP handle to disconnect a peer and refuse connections to or from it for a duration.
+///
+/// Handles `POST /network/peers/{peer_id}/ban`, returning the time the ban expires.
+pub fn post_ban_peer<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let peer_id = peer_id_from_path(req.uri().path())?;
+
+    let body = req
+        .extensions()
+        .get::<RequestBody>()
+        .map_or(&[][..], |body| body.0.as_slice());
+    let ban_request = if body.is_empty() {
+        BanRequest::default()
+    } else {
+        serde_json::from_slice::<BanRequest>(body).map_err(|e| {
+            ApiError::InvalidQueryParams(format!("Invalid ban request body: {:?}", e))
+        })?
+    };
+    if let Some(duration_secs) = ban_request.duration_secs {
+        if duration_secs > MAX_BAN_DURATION.as_secs() {
+            return Err(ApiError::InvalidQueryParams(format!(
+                "Ban duration of {} seconds exceeds the maximum of {} seconds",
+                duration_secs,
+                MAX_BAN_DURATION.as_secs()
+            )));
+        }
+    }
+
+    let expiry = network.ban_peer(
+        peer_id,
+        ban_request.duration_secs.map(Duration::from_secs),
+        "Banned via the REST API".to_string(),
+    );
+    let response = BanResponse {
+        ban_expiry: unix_seconds(expiry),
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize BanResponse: {:?}", e))
+        })?,
+    )))
+}
+
+/// HTTP handle to lift the ban on a peer.
+///
+/// Handles `POST /network/peers/{peer_id}/unban`, returning `204 No Content`. Returns `404` if the
+/// peer is not banned.
+pub fn post_unban_peer<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let peer_id = peer_id_from_path(req.uri().path())?;
+
+    if network.unban_peer(peer_id) {
+        Ok(success_response_with_status(
+            StatusCode::NO_CONTENT,
+            Body::empty(),
+        ))
+    } else {
+        Err(ApiError::NotFound("Peer is not banned".to_string()))
+    }
+}
+
+#[derive(Serialize)]
+pub struct DiscoverResponse {
+    /// The number of recently seen peer ENRs when the search was started.
+    pub known_enrs: usize,
+}
+
+/// HTTP handle to start a discv5 peer search immediately, rather than waiting for the next
+/// scheduled search.
+///
+/// Handles `POST /network/discover`. The search runs in the background so `202 Accepted` is
+/// returned; compare `known_enrs` with a later response to see what it found. Returns `429` if a
+/// search was requested within the last few seconds.
+pub fn post_discover<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    if !network.discovery_enabled() {
+        return Err(ApiError::Forbidden("Discovery is disabled".to_string()));
+    }
+
+    let known_enrs = network.request_peer_search().map_err(|wait| {
+        ApiError::TooManyRequests(format!(
+            "A peer search was requested recently, retry in {} seconds",
+            wait.as_secs() + 1
+        ))
+    })?;
+    let response = DiscoverResponse { known_enrs };
+
+    Ok(success_response_with_status(
+        StatusCode::ACCEPTED,
+        Body::from(serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize DiscoverResponse: {:?}", e))
+        })?),
+    ))
+}
+
+/// Returns `time` as seconds since the unix epoch.
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Serialize)]
+pub struct BootnodeStatus {
+    /// The boot node ENR as base64, or the multiaddr, as configured.
+    pub addr: String,
+    /// `true` if a libp2p connection to the boot node is open.
+    pub connected: bool,
+    /// The peer id of the boot node, or `None` if it is a multiaddr without a `/p2p` component
+    /// which no connected peer is using.
+    pub peer_id: Option<String>,
+}
+
+/// HTTP handle to report whether the client is connected to each of its configured boot nodes
+/// and startup multiaddrs.
+pub fn get_bootnode_status<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let connected_peers = network.connected_peer_set();
+    let peer_addresses = network.peer_addresses();
+
+    let enr_status = network.boot_nodes().iter().map(|enr| {
+        let peer_id = enr.peer_id();
+        BootnodeStatus {
+            addr: enr.to_base64(),
+            connected: connected_peers.contains(&peer_id),
+            peer_id: Some(peer_id.to_string()),
+        }
+    });
+
+    let multiaddr_status = network.libp2p_nodes().iter().map(|multiaddr| {
+        let peer_id = multiaddr
+            .iter()
+            .find_map(|protocol| match protocol {
+                Protocol::P2p(multihash) => PeerId::from_multihash(multihash).ok(),
+                _ => None,
+            })
+            .or_else(|| {
+                peer_addresses
+                    .iter()
+                    .find(|(_, addr)| *addr == multiaddr)
+                    .map(|(peer_id, _)| peer_id.clone())
+            });
+        BootnodeStatus {
+            addr: multiaddr.to_string(),
+            connected: peer_id
+                .as_ref()
+                .map_or(false, |peer_id| connected_peers.contains(peer_id)),
+            peer_id: peer_id.map(|peer_id| peer_id.to_string()),
+        }
+    });
+
+    let status: Vec<BootnodeStatus> = enr_status.chain(multiaddr_status).collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&status).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize bootnode status: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct PeerAddrInfo {
+    /// The remote address of the connection to the peer.
+    pub addr: String,
+    /// `false` if the address is loopback, private, link-local or otherwise not publicly
+    /// routable.
+    pub is_global: bool,
+    /// Either `v4` or `v6`, or `None` if the address does not contain an IP (e.g., DNS).
+    pub family: Option<String>,
+}
+
+/// HTTP handle to classify the remote address of each peer connected to the client's libp2p
+/// service.
+///
+/// Returns a map of `PeerId::to_string()` to `PeerAddrInfo`.
+pub fn get_peer_addr_info<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let addr_info: HashMap<String, PeerAddrInfo> = network
+        .peer_addresses()
+        .into_iter()
+        .map(|(peer_id, addr)| {
+            let ip = addr.iter().find_map(|protocol| match protocol {
+                Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+                Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            });
+            let info = PeerAddrInfo {
+                addr: addr.to_string(),
+                is_global: ip.map_or(false, is_global),
+                family: ip.map(|ip| if ip.is_ipv4() { "v4" } else { "v6" }.to_string()),
+            };
+            (peer_id.to_string(), info)
+        })
+        .collect();
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&addr_info).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize peer addresses: {:?}", e))
+        })?,
+    )))
+}
+
+/// Returns `true` if `ip` is publicly routable.
+///
+/// A stand-in for the unstable `IpAddr::is_global`.
+fn is_global(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            // 0.0.0.0/8, "this network".
+            let is_this_network = octets[0] == 0;
+            // 100.64.0.0/10, shared address space used by carrier-grade NAT.
+            let is_shared = octets[0] == 100 && (octets[1] & 0b1100_0000) == 0b0100_0000;
+            // 192.0.0.0/24, IETF protocol assignments.
+            let is_protocol_assignment = octets[0] == 192 && octets[1] == 0 && octets[2] == 0;
+            // 198.18.0.0/15, benchmarking.
+            let is_benchmarking = octets[0] == 198 && (octets[1] & 0xfe) == 18;
+            // 240.0.0.0/4, reserved, which includes the broadcast address.
+            let is_reserved = (octets[0] & 0xf0) == 0xf0;
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_documentation()
+                || is_this_network
+                || is_shared
+                || is_protocol_assignment
+                || is_benchmarking
+                || is_reserved)
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            // ::ffff:0:0/96, IPv4-mapped addresses, which are as global as the IPv4 address.
+            if segments[..5].iter().all(|segment| *segment == 0) && segments[5] == 0xffff {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_global(IpAddr::V4(Ipv4Addr::new(a, b, c, d)));
+            }
+            // fc00::/7, unique local addresses.
+            let is_unique_local = (segments[0] & 0xfe00) == 0xfc00;
+            // fe80::/10, link-local unicast addresses.
+            let is_link_local = (segments[0] & 0xffc0) == 0xfe80;
+            // 2001:db8::/32, documentation.
+            let is_documentation = segments[0] == 0x2001 && segments[1] == 0x0db8;
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || is_unique_local
+                || is_link_local
+                || is_documentation)
+        }
+    }
+}
+
+/// The number of slots our head may be behind the highest peer head whilst still being considered
+/// synced.
+const SYNCED_TOLERANCE_SLOTS: u64 = 1;
+
+#[derive(Serialize)]
+pub struct SyncStatusResponse {
+    /// `true` if our head is within `SYNCED_TOLERANCE_SLOTS` of the highest peer head.
+    pub is_synced: bool,
+    /// The highest head slot advertised by a connected peer, if any.
+    pub peer_head_slot: Option<u64>,
+    /// The slot of our head block.
+    pub node_head_slot: u64,
+    /// Our head slot as a percentage of the highest peer head slot.
+    pub sync_percentage: Option<f64>,
+}
+
+/// HTTP handle to return whether the node believes it is synced with its peers.
+///
+/// A node without any peers is never considered synced.
+pub fn get_sync_status<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+    let beacon_chain = req
+        .extensions()
+        .get::<Arc<BeaconChain<T>>>()
+        .ok_or_else(|| ApiError::ServerError("Beacon chain extension missing".to_string()))?;
+
+    let node_head_slot = beacon_chain.head().beacon_block.slot.as_u64();
+    let peer_head_slot = network.highest_peer_head_slot().map(|slot| slot.as_u64());
+
+    let response = SyncStatusResponse {
+        is_synced: peer_head_slot.map_or(false, |peer| {
+            node_head_slot + SYNCED_TOLERANCE_SLOTS >= peer
+        }),
+        peer_head_slot,
+        node_head_slot,
+        sync_percentage: peer_head_slot.map(|peer| {
+            if peer == 0 {
+                100.0
+            } else {
+                (node_head_slot as f64 / peer as f64 * 100.0).min(100.0)
+            }
+        }),
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize SyncStatusResponse: {:?}", e))
+        })?,
+    )))
+}
+
+#[derive(Serialize)]
+pub struct NodeVersionResponse {
+    /// The agent string advertised to peers via identify.
+    pub agent_version: String,
+    /// The operator-chosen name included in `agent_version`, if any.
+    pub moniker: Option<String>,
+    /// The libp2p protocol version advertised to peers via identify.
+    pub protocol_version: String,
+    /// The ids of the stream protocols offered to peers.
+    pub protocols: Vec<String>,
+    /// `true` if the node is configured as a bootnode.
+    pub is_bootnode: bool,
+    /// `true` if the node relays the traffic of every attestation subnet.
+    pub relay_enabled: bool,
+}
+
+/// HTTP handle to return the identity information this node presents to its peers.
+pub fn get_node_version<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let response = NodeVersionResponse {
+        agent_version: network.agent_version().to_string(),
+        moniker: network.moniker().map(str::to_string),
+        protocol_version: PROTOCOL_VERSION.to_string(),
+        protocols: network.supported_protocols(),
+        is_bootnode: network.is_bootnode(),
+        relay_enabled: network.relay_enabled(),
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize NodeVersionResponse: {:?}", e))
+        })?,
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn client_family_works() {
+        assert_eq!(
+            client_family(Some("Lighthouse/v0.1.0-unstable/x86_64-linux")),
+            "Lighthouse"
+        );
+        assert_eq!(client_family(Some("Prysm/v0.2.7/abcdef")), "Prysm");
+        assert_eq!(client_family(Some("teku/v0.9.2")), "Teku");
+        assert_eq!(client_family(Some("js-libp2p/0.26.2")), "Lodestar");
+        assert_eq!(client_family(Some("rust-libp2p/0.13.0")), "unknown");
+        assert_eq!(client_family(None), "unknown");
+    }
+
+    #[test]
+    fn is_global_excludes_special_ranges() {
+        let not_global = [
+            "0.1.2.3",
+            "10.0.0.1",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.0.1",
+            "172.16.0.1",
+            "192.0.0.8",
+            "192.0.2.1",
+            "192.168.1.1",
+            "198.18.0.1",
+            "198.19.255.255",
+            "240.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fc00::1",
+            "fe80::1",
+            "2001:db8::1",
+            "::ffff:10.0.0.1",
+            "::ffff:127.0.0.1",
+        ];
+        for ip in &not_global {
+            assert!(
+                !is_global(ip.parse().unwrap()),
+                "{} should not be global",
+                ip
+            );
+        }
+
+        let global = [
+            "1.1.1.1",
+            "100.128.0.1",
+            "192.0.1.1",
+            "198.20.0.1",
+            "2001:4860::8888",
+            "::ffff:1.1.1.1",
+        ];
+        for ip in &global {
+            assert!(is_global(ip.parse().unwrap()), "{} should be global", ip);
+        }
+    }
+}
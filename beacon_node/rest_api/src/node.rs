@@ -1,18 +1,91 @@
-use crate::{success_response, ApiResult};
+use crate::network::get_network_service;
+use crate::{success_response, ApiError, ApiResult, StartTime};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use hyper::{Body, Request};
+use serde::Serialize;
 use std::sync::Arc;
+use tree_hash::TreeHash;
+use types::{BeaconBlock, BeaconState, Hash256};
 use version;
 
-/// Read the version string from the current Lighthouse build.
-pub fn get_version(_req: Request<Body>) -> ApiResult {
+#[derive(Serialize)]
+pub struct VersionResponse {
+    /// The semantic version, e.g., `0.1.0`.
+    pub version: String,
+    /// The full version string, as advertised to peers.
+    pub agent_version: String,
+    /// The git commit the binary was built from, or `unknown`.
+    pub commit: String,
+    /// The target triple the binary was built for.
+    pub target: String,
+    pub uptime_seconds: u64,
+}
+
+/// Read the version, build information and uptime of the current Lighthouse build.
+pub fn get_version(req: Request<Body>) -> ApiResult {
+    let response = VersionResponse {
+        version: version::semantic_version().to_string(),
+        agent_version: version::version(),
+        commit: version::commit().to_string(),
+        target: version::build_target().to_string(),
+        uptime_seconds: uptime_seconds(&req)?,
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize VersionResponse: {:?}", e))
+        })?,
+    )))
+}
+
+/// Read the number of seconds since the node started.
+pub fn get_uptime(req: Request<Body>) -> ApiResult {
     let body = Body::from(
-        serde_json::to_string(&version::version())
-            .expect("Version should always be serialializable as JSON."),
+        serde_json::to_string(&uptime_seconds(&req)?)
+            .expect("Uptime should always be serializable as JSON."),
     );
     Ok(success_response(body))
 }
 
+fn uptime_seconds(req: &Request<Body>) -> Result<u64, ApiError> {
+    req.extensions()
+        .get::<StartTime>()
+        .map(|start_time| start_time.0.elapsed().as_secs())
+        .ok_or_else(|| ApiError::ServerError("StartTime extension missing".to_string()))
+}
+
+#[derive(Serialize)]
+pub struct HealthResponse {
+    pub connected_peers: usize,
+    pub minimum_peers: usize,
+}
+
+/// Report whether the node is ready to serve traffic, i.e., whether it has at least
+/// `--minimum-peers` connected peers.
+///
+/// Returns `503 Service Unavailable` whilst the node is not ready.
+pub fn get_health<T: BeaconChainTypes>(req: Request<Body>) -> ApiResult {
+    let network = get_network_service::<T>(&req)?;
+
+    let response = HealthResponse {
+        connected_peers: network.connected_peers(),
+        minimum_peers: network.minimum_peers(),
+    };
+
+    if response.connected_peers < response.minimum_peers {
+        return Err(ApiError::ServiceUnavailable(format!(
+            "Not ready, connected to {} of a minimum of {} peers",
+            response.connected_peers, response.minimum_peers
+        )));
+    }
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize HealthResponse: {:?}", e))
+        })?,
+    )))
+}
+
 /// Read the genesis time from the current beacon chain state.
 pub fn get_genesis_time<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult {
     let beacon_chain = req.extensions().get::<Arc<BeaconChain<T>>>().unwrap();
@@ -23,3 +96,49 @@ pub fn get_genesis_time<T: BeaconChainTypes + 'static>(req: Request<Body>) -> Ap
     );
     Ok(success_response(body))
 }
+
+#[derive(Serialize)]
+pub struct GenesisInfo {
+    pub genesis_time: u64,
+    /// The `0x`-prefixed hex fork version of the genesis state.
+    pub genesis_fork_version: String,
+    /// The tree hash root of the validators in the genesis state.
+    pub genesis_validators_root: Hash256,
+}
+
+/// HTTP handler to return the genesis time, fork version and validators root.
+///
+/// Returns a `503` if the genesis time has not yet been reached.
+pub fn get_genesis_info<T: BeaconChainTypes + 'static>(req: Request<Body>) -> ApiResult {
+    let beacon_chain = req
+        .extensions()
+        .get::<Arc<BeaconChain<T>>>()
+        .ok_or_else(|| ApiError::ServerError("Beacon chain extension missing".to_string()))?;
+
+    if beacon_chain.read_slot_clock().is_none() {
+        return Err(ApiError::ServiceUnavailable(
+            "Genesis has not yet occurred".to_string(),
+        ));
+    }
+
+    let genesis_block: BeaconBlock<T::EthSpec> = beacon_chain
+        .store
+        .get(&beacon_chain.genesis_block_root)?
+        .ok_or_else(|| ApiError::NotFound("Unable to find genesis block".to_string()))?;
+    let genesis_state: BeaconState<T::EthSpec> = beacon_chain
+        .store
+        .get(&genesis_block.state_root)?
+        .ok_or_else(|| ApiError::NotFound("Unable to find genesis state".to_string()))?;
+
+    let response = GenesisInfo {
+        genesis_time: genesis_state.genesis_time,
+        genesis_fork_version: format!("0x{}", hex::encode(genesis_state.fork.current_version)),
+        genesis_validators_root: Hash256::from_slice(&genesis_state.validators.tree_hash_root()),
+    };
+
+    Ok(success_response(Body::from(
+        serde_json::to_string(&response).map_err(|e| {
+            ApiError::ServerError(format!("Unable to serialize GenesisInfo: {:?}", e))
+        })?,
+    )))
+}
@@ -11,7 +11,7 @@ use self::validator::ValidatorServiceInstance;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 pub use config::Config as RPCConfig;
 use futures::Future;
-use grpcio::{Environment, ServerBuilder};
+use grpcio::{Environment, ServerBuilder, ServerCredentialsBuilder};
 use network::NetworkMessage;
 use protos::services_grpc::{
     create_attestation_service, create_beacon_block_service, create_beacon_node_service,
@@ -28,7 +28,7 @@ pub fn start_server<T: BeaconChainTypes + Clone + 'static>(
     network_chan: mpsc::UnboundedSender<NetworkMessage>,
     beacon_chain: Arc<BeaconChain<T>>,
     log: &slog::Logger,
-) -> exit_future::Signal {
+) -> Result<exit_future::Signal, String> {
     let log = log.new(o!("Service"=>"RPC"));
     let env = Arc::new(Environment::new(1));
 
@@ -68,14 +68,29 @@ pub fn start_server<T: BeaconChainTypes + Clone + 'static>(
         create_attestation_service(instance)
     };
 
-    let mut server = ServerBuilder::new(env)
+    let builder = ServerBuilder::new(env)
         .register_service(beacon_block_service)
         .register_service(validator_service)
         .register_service(beacon_node_service)
-        .register_service(attestation_service)
-        .bind(config.listen_address.to_string(), config.port)
+        .register_service(attestation_service);
+
+    // The TLS files are checked when the config is loaded, but may have changed since.
+    let tls_credentials = config
+        .tls_credentials()
+        .map_err(|e| format!("Unable to load RPC TLS credentials: {}", e))?;
+
+    let builder = match tls_credentials {
+        Some((cert, key)) => {
+            info!(log, "gRPC using TLS");
+            let credentials = ServerCredentialsBuilder::new().add_cert(cert, key).build();
+            builder.bind_secure(config.listen_address.to_string(), config.port, credentials)
+        }
+        None => builder.bind(config.listen_address.to_string(), config.port),
+    };
+
+    let mut server = builder
         .build()
-        .unwrap();
+        .map_err(|e| format!("Unable to start the gRPC server: {:?}", e))?;
 
     let spawn_rpc = {
         server.start();
@@ -93,5 +108,5 @@ pub fn start_server<T: BeaconChainTypes + Clone + 'static>(
         })
     };
     executor.spawn(spawn_rpc);
-    rpc_exit_signal
+    Ok(rpc_exit_signal)
 }
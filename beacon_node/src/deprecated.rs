@@ -0,0 +1,151 @@
+use std::ffi::OsString;
+
+/// What became of a flag that is no longer defined.
+pub enum Deprecation {
+    /// The flag is now called by the given name, and takes the same values.
+    Renamed(&'static str),
+    /// The flag no longer has any effect. If `takes_value`, the value following the flag is also
+    /// dropped.
+    Removed {
+        takes_value: bool,
+        reason: &'static str,
+    },
+}
+
+/// Flags that are no longer defined but are still accepted, by long name without the `--`.
+pub const DEPRECATED_FLAGS: &[(&str, Deprecation)] =
+    &[("discovery-port", Deprecation::Renamed("disc-port"))];
+
+/// Rewrites any of the `deprecated` flags in `args` so that clap accepts them: renamed flags are
+/// replaced by their new name and removed flags are dropped.
+///
+/// Returns the new arguments and, for each deprecated flag found, the flag and a message for the
+/// user. Arguments after a `--` are left untouched.
+pub fn rewrite_args(
+    args: Vec<OsString>,
+    deprecated: &[(&str, Deprecation)],
+) -> (Vec<OsString>, Vec<(String, String)>) {
+    let mut rewritten = Vec::with_capacity(args.len());
+    let mut warnings = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let flag = match arg.to_str().map(str::to_string) {
+            Some(ref flag) if flag == "--" => {
+                rewritten.push(arg);
+                rewritten.extend(args.by_ref());
+                break;
+            }
+            Some(flag) if flag.starts_with("--") => flag,
+            _ => {
+                rewritten.push(arg);
+                continue;
+            }
+        };
+
+        // Flags may be given as either `--flag value` or `--flag=value`.
+        let (name, inline_value) = match flag[2..].find('=') {
+            Some(i) => (&flag[2..i + 2], Some(&flag[i + 3..])),
+            None => (&flag[2..], None),
+        };
+
+        match deprecated.iter().find(|(old, _)| *old == name) {
+            Some((old, Deprecation::Renamed(new))) => {
+                warnings.push((
+                    format!("--{}", old),
+                    format!("--{} has been renamed to --{}", old, new),
+                ));
+                rewritten.push(match inline_value {
+                    Some(value) => format!("--{}={}", new, value).into(),
+                    None => format!("--{}", new).into(),
+                });
+            }
+            Some((
+                old,
+                Deprecation::Removed {
+                    takes_value,
+                    reason,
+                },
+            )) => {
+                warnings.push((
+                    format!("--{}", old),
+                    format!("--{} has been removed and is ignored: {}", old, reason),
+                ));
+                if *takes_value && inline_value.is_none() {
+                    args.next();
+                }
+            }
+            None => rewritten.push(arg),
+        }
+    }
+
+    (rewritten, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_FLAGS: &[(&str, Deprecation)] = &[
+        ("old-port", Deprecation::Renamed("port")),
+        (
+            "old-size",
+            Deprecation::Removed {
+                takes_value: true,
+                reason: "no longer needed",
+            },
+        ),
+        (
+            "old-switch",
+            Deprecation::Removed {
+                takes_value: false,
+                reason: "now the default",
+            },
+        ),
+    ];
+
+    fn rewrite(args: &[&str]) -> (Vec<String>, usize) {
+        let (args, warnings) = rewrite_args(args.iter().map(OsString::from).collect(), TEST_FLAGS);
+        let args = args
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect();
+        (args, warnings.len())
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn renamed_flags_are_replaced() {
+        assert_eq!(
+            rewrite(&["bn", "--old-port", "9000", "--old-port=9001"]),
+            (strings(&["bn", "--port", "9000", "--port=9001"]), 2)
+        );
+    }
+
+    #[test]
+    fn removed_flags_are_dropped() {
+        assert_eq!(
+            rewrite(&[
+                "bn",
+                "--old-size",
+                "5",
+                "--old-size=5",
+                "--old-switch",
+                "--port",
+                "1"
+            ]),
+            (strings(&["bn", "--port", "1"]), 3)
+        );
+    }
+
+    #[test]
+    fn args_after_double_dash_are_kept() {
+        assert_eq!(
+            rewrite(&["bn", "--", "--old-port"]),
+            (strings(&["bn", "--", "--old-port"]), 0)
+        );
+    }
+}
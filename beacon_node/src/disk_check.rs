@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::Path;
+
+/// The file written and removed in a directory to check that it is writable.
+const PROBE_FILENAME: &str = ".write_probe";
+
+/// The number of bytes in a megabyte, as given to `--min-free-space`.
+pub const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Checks that a file can be written to and removed from `data_dir`, and that the disk holding it
+/// has at least `min_free_space_mb` megabytes available.
+///
+/// Returns the number of available bytes.
+pub fn check_datadir(data_dir: &Path, min_free_space_mb: u64) -> Result<u64, String> {
+    check_writable(data_dir).map_err(|e| {
+        format!(
+            "The datadir {:?} is not writable, it may be on a read-only or full disk: {}",
+            data_dir, e
+        )
+    })?;
+
+    let available = available_space(data_dir)?;
+
+    if available < min_free_space_mb.saturating_mul(BYTES_PER_MB) {
+        return Err(format!(
+            "The disk holding the datadir {:?} has {} MB free, less than the {} MB required by --min-free-space",
+            data_dir,
+            available / BYTES_PER_MB,
+            min_free_space_mb
+        ));
+    }
+
+    Ok(available)
+}
+
+/// Writes and removes a probe file in `dir`, returning an error if either fails.
+pub fn check_writable(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(PROBE_FILENAME);
+    fs::write(&probe, b"lighthouse")
+        .map_err(|e| format!("Unable to write probe file {:?}: {}", probe, e))?;
+    fs::remove_file(&probe).map_err(|e| format!("Unable to remove probe file {:?}: {}", probe, e))
+}
+
+/// Returns the number of bytes available to this user on the disk holding `dir`.
+pub fn available_space(dir: &Path) -> Result<u64, String> {
+    fs2::available_space(dir).map_err(|e| {
+        format!(
+            "Unable to read the free space of the disk holding {:?}: {}",
+            dir, e
+        )
+    })
+}
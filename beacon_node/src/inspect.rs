@@ -0,0 +1,214 @@
+use client::{ClientConfig, Eth2Config};
+use eth2_config::{checksum_path, read_from_file, read_from_file_verified};
+use eth2_libp2p::peer_id_from_network_dir;
+use serde::{de::DeserializeOwned, Serialize};
+use slog::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A summary of a datadir, as reported by the `inspect` subcommand.
+///
+/// The field names are part of the `--json` output and should not be changed.
+#[derive(Serialize)]
+pub struct DatadirSummary {
+    pub datadir: PathBuf,
+    pub version: String,
+    pub client_config: Option<ConfigSummary<ClientConfig>>,
+    pub eth2_config: Option<ConfigSummary<Eth2Config>>,
+    pub db_type: Option<String>,
+    /// The size of the on-disk database, or `None` if there is none.
+    pub db_size_bytes: Option<u64>,
+    pub peer_id: Option<String>,
+    /// Problems found with the datadir.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigSummary<T> {
+    pub file: PathBuf,
+    /// `true` if the file has a checksum, which it matched.
+    pub verified: bool,
+    pub config: T,
+}
+
+/// Reports the configuration, database and network key found in `data_dir`, either as log
+/// messages or, if `json` is set, as a single JSON document on stdout.
+///
+/// The configs are read from `client_config_name` and `eth2_config_name` within `data_dir`.
+///
+/// Nothing is written and no services are started. Problems with the datadir are reported as
+/// warnings rather than errors, so that as much as possible is reported.
+pub fn inspect_datadir(
+    data_dir: &Path,
+    client_config_name: &str,
+    eth2_config_name: &str,
+    json: bool,
+    log: &slog::Logger,
+) {
+    let summary = summarize_datadir(data_dir, client_config_name, eth2_config_name);
+
+    if json {
+        match serde_json::to_string_pretty(&summary) {
+            Ok(json) => println!("{}", json),
+            Err(e) => warn!(log, "Unable to encode datadir summary"; "error" => format!("{}", e)),
+        }
+    } else {
+        log_summary(&summary, log);
+    }
+}
+
+fn summarize_datadir(
+    data_dir: &Path,
+    client_config_name: &str,
+    eth2_config_name: &str,
+) -> DatadirSummary {
+    let mut summary = DatadirSummary {
+        datadir: data_dir.to_path_buf(),
+        version: version::version(),
+        client_config: None,
+        eth2_config: None,
+        db_type: None,
+        db_size_bytes: None,
+        peer_id: None,
+        warnings: vec![],
+    };
+
+    if !data_dir.is_dir() {
+        summary
+            .warnings
+            .push(format!("Datadir does not exist: {:?}", data_dir));
+        return summary;
+    }
+
+    summary.client_config = read_config(data_dir.join(client_config_name), &mut summary.warnings);
+    summary.eth2_config = read_config(data_dir.join(eth2_config_name), &mut summary.warnings);
+
+    let client_config = match &summary.client_config {
+        Some(client_config) => client_config.config.clone(),
+        None => return summary,
+    };
+
+    if let Some(eth2_config) = &summary.eth2_config {
+        if eth2_config.config.spec_constants != client_config.spec_constants {
+            summary.warnings.push(format!(
+                "Specification constants do not match: client_config {}, eth2_config {}",
+                client_config.spec_constants, eth2_config.config.spec_constants
+            ));
+        }
+    }
+
+    summary.db_type = Some(client_config.db_type.clone());
+    if let Some(db_path) = client_config.db_path_unchecked().filter(|p| p.exists()) {
+        match dir_size(&db_path) {
+            Ok(size) => summary.db_size_bytes = Some(size),
+            Err(e) => summary
+                .warnings
+                .push(format!("Unable to read database size: {}", e)),
+        }
+    }
+
+    let network_dir = client_config.network.network_dir.clone();
+    match peer_id_from_network_dir(&network_dir) {
+        Ok(Some(peer_id)) => summary.peer_id = Some(peer_id.to_string()),
+        Ok(None) => summary.warnings.push(format!(
+            "No network key in {:?}, a new one will be generated on start",
+            network_dir
+        )),
+        Err(e) => summary.warnings.push(format!("Invalid network key: {}", e)),
+    }
+
+    summary
+}
+
+fn log_summary(summary: &DatadirSummary, log: &slog::Logger) {
+    info!(
+        log,
+        "Inspecting datadir";
+        "datadir" => format!("{:?}", summary.datadir),
+        "version" => &summary.version,
+    );
+
+    if let Some(client_config) = &summary.client_config {
+        info!(
+            log,
+            "Config";
+            "config" => "ClientConfig",
+            "file" => format!("{:?}", client_config.file),
+            "verified" => client_config.verified,
+        );
+    }
+    if let Some(eth2_config) = &summary.eth2_config {
+        info!(
+            log,
+            "Config";
+            "config" => "Eth2Config",
+            "file" => format!("{:?}", eth2_config.file),
+            "verified" => eth2_config.verified,
+        );
+        info!(log, "Spec"; "spec_constants" => &eth2_config.config.spec_constants);
+    }
+    if let Some(db_type) = &summary.db_type {
+        info!(
+            log,
+            "Database";
+            "db_type" => db_type,
+            "size_bytes" => format!("{:?}", summary.db_size_bytes),
+        );
+    }
+    if let Some(peer_id) = &summary.peer_id {
+        info!(log, "Network key"; "peer_id" => peer_id);
+    }
+
+    for warning in &summary.warnings {
+        warn!(log, "Datadir problem"; "warning" => warning);
+    }
+}
+
+/// Reads the config at `path`, recording whether it matches its checksum.
+///
+/// A config which fails verification is still read, so that it can be reported.
+fn read_config<T>(path: PathBuf, warnings: &mut Vec<String>) -> Option<ConfigSummary<T>>
+where
+    T: Default + DeserializeOwned + Serialize,
+{
+    let has_checksum = checksum_path(&path).exists();
+    let (config, verified) = match read_from_file_verified::<T>(path.clone()) {
+        Ok(config) => (config, has_checksum),
+        Err(e) => {
+            warnings.push(e);
+            let config = read_from_file::<T>(path.clone()).unwrap_or_else(|e| {
+                warnings.push(e);
+                None
+            });
+            (config, false)
+        }
+    };
+
+    match config {
+        Some(config) => Some(ConfigSummary {
+            file: path,
+            verified,
+            config,
+        }),
+        None => {
+            warnings.push(format!("Config file does not exist: {:?}", path));
+            None
+        }
+    }
+}
+
+/// Returns the total size in bytes of the files within `path`.
+pub fn dir_size(path: &Path) -> Result<u64, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))? {
+        let entry = entry.map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+        size += dir_size(&entry.path())?;
+    }
+
+    Ok(size)
+}
@@ -1,22 +1,37 @@
+mod deprecated;
+mod disk_check;
+mod inspect;
 mod run;
 
-use clap::{App, Arg};
-use client::{ClientConfig, Eth2Config};
+use clap::{App, AppSettings, Arg, SubCommand};
+use client::{Bootstrapper, ClientConfig, Eth2Config, DEFAULT_LOG_BUFFER_SIZE, PROFILES};
 use env_logger::{Builder, Env};
-use eth2_config::{read_from_file, write_to_file};
-use slog::{crit, o, warn, Drain, Level};
+use eth2_config::{
+    checksum_path, read_from_file, read_from_file_verified, unknown_keys, write_to_file,
+    Eth2Network,
+};
+use slog::{crit, info, o, warn, Drain, Level};
+use slog_async::OverflowStrategy;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse";
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
 pub const TESTNET_CONFIG_FILENAME: &str = "testnet.toml";
+/// The file in the datadir from which the log level is re-read on SIGHUP.
+pub const LOG_LEVEL_FILENAME: &str = "log_level";
 
 fn main() {
     // debugging output for libp2p and external crates
     Builder::from_env(Env::default()).init();
 
+    // Deprecated flags are rewritten before clap sees them, then reported once there is a logger.
+    let (args, deprecation_warnings) =
+        deprecated::rewrite_args(std::env::args_os().collect(), deprecated::DEPRECATED_FLAGS);
+
     let matches = App::new("Lighthouse")
         .version(version::version().as_str())
         .author("Sigma Prime <contact@sigmaprime.io>")
@@ -31,6 +46,24 @@ fn main() {
                 .help("Data directory for keys and databases.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("client-config-name")
+                .long("client-config-name")
+                .value_name("FILENAME")
+                .help("The filename of the ClientConfig within the datadir. Allows several profiles to share one datadir.")
+                .takes_value(true)
+                .validator(validate_file_name)
+                .default_value(CLIENT_CONFIG_FILENAME),
+        )
+        .arg(
+            Arg::with_name("eth2-config-name")
+                .long("eth2-config-name")
+                .value_name("FILENAME")
+                .help("The filename of the Eth2Config within the datadir. Allows several profiles to share one datadir.")
+                .takes_value(true)
+                .validator(validate_file_name)
+                .default_value(ETH2_CONFIG_FILENAME),
+        )
         .arg(
             Arg::with_name("logfile")
                 .long("logfile")
@@ -38,12 +71,89 @@ fn main() {
                 .help("File path where output will be written.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("data-dir-permissions")
+                .long("data-dir-permissions")
+                .value_name("PERMISSIONS")
+                .help("On Unix, \"restricted\" makes the datadir, network dir and network key accessible only by the current user and warns if the network key is readable by others. \"default\" leaves permissions to the umask.")
+                .takes_value(true)
+                .possible_values(&["restricted", "default"])
+                .default_value("restricted"),
+        )
         .arg(
             Arg::with_name("network-dir")
                 .long("network-dir")
                 .value_name("NETWORK-DIR")
-                .help("Data directory for network keys.")
+                .help("Data directory for the network key and peer cache. Defaults to the network directory inside the datadir.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("PROFILE")
+                .help("A preset of settings for a common deployment, which explicit flags override. \"staker\" enables the RPC and REST APIs with 25 peers, \"infra-bootnode\" runs a discoverable bootnode subscribed to all subnets with 100 peers and \"dev\" enables the RPC and REST APIs with 5 peers.")
+                .takes_value(true)
+                .possible_values(PROFILES),
+        )
+        .arg(
+            Arg::with_name("testnet-dir")
+                .long("testnet-dir")
+                .value_name("DIR")
+                .help("A directory containing a testnet definition: an eth2-spec.toml and optionally a beacon-node.toml, boot_enr.txt and genesis_state.yaml.")
+                .takes_value(true)
+                .conflicts_with_all(&["default-spec", "eth2-config"])
+        )
+        .arg(
+            Arg::with_name("network")
+                .long("network")
+                .value_name("NAME")
+                .help("Join a well-known network, setting its spec, chain id and boot nodes. A spec given by --testnet-dir, --default-spec or --eth2-config and boot nodes given by --boot-nodes override those of the network. One of local or interop.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Parse and validate all configuration (including any remote downloads), print a summary and exit without creating the datadir or writing any files.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("min-free-space")
+                .long("min-free-space")
+                .value_name("MEGABYTES")
+                .help("Refuse to start unless the disk holding the datadir has at least this much free space. The datadir is also checked to be writable.")
                 .takes_value(true)
+                .default_value("1024")
+                .validator(|size| match size.parse::<u64>() {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(format!("must be a number of megabytes, not {}", size)),
+                }),
+        )
+        .arg(
+            Arg::with_name("testnet-reset")
+                .long("testnet-reset")
+                .help("Delete (without a backup, unless --backup-dir is given) the database and configuration in the datadir, then recreate them from the spec given by --testnet-dir, --default-spec or --eth2-config. The network key is kept. Asks for confirmation unless --yes is given and refuses to reset a mainnet spec.")
+                .takes_value(false)
+                .conflicts_with("dry-run"),
+        )
+        .arg(
+            Arg::with_name("backup-dir")
+                .long("backup-dir")
+                .value_name("DIR")
+                .help("Copy the database and configuration into a new backup_<timestamp> directory in DIR before --testnet-reset deletes them. DIR may be on another disk; it must be writable and have room for the backup.")
+                .takes_value(true)
+                .requires("testnet-reset"),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .long("yes")
+                .help("Answer yes to any confirmation prompt, e.g. that of --testnet-reset.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("ignore-config-checksum")
+                .long("ignore-config-checksum")
+                .help("Load config files from the datadir even if they do not match their .sha256 checksum. Use this if you have intentionally edited a config file by hand.")
+                .takes_value(false),
         )
         /*
          * Network parameters.
@@ -52,8 +162,10 @@ fn main() {
             Arg::with_name("listen-address")
                 .long("listen-address")
                 .value_name("ADDRESS")
-                .help("The address lighthouse will listen for UDP and TCP connections. (default 127.0.0.1).")
-                .takes_value(true),
+                .help("The address lighthouse will listen for UDP and TCP connections. (default 127.0.0.1). May be repeated or be a comma-separated list, in which case libp2p listens on every address and discovery on the first.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
         )
         .arg(
             Arg::with_name("port")
@@ -68,14 +180,85 @@ fn main() {
                 .help("The maximum number of peers (default 10).")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("minimum-peers")
+                .long("minimum-peers")
+                .value_name("PEERS")
+                .help("The number of connected peers below which GET /node/health reports the node as not ready (default 1).")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ban-duration")
+                .long("ban-duration")
+                .value_name("SECONDS")
+                .help("The default number of seconds a peer is banned for via the HTTP API (default 3600, at most a year).")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("connect-timeout")
+                .long("connect-timeout")
+                .value_name("SECONDS")
+                .help("The number of seconds a peer connection may take to be established before it is abandoned (default 10). Abandoned dials are recorded as timeouts in the dial history.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("idle-connection-timeout")
+                .long("idle-connection-timeout")
+                .value_name("SECONDS")
+                .help("The number of seconds the eth2 RPC keeps a connection to a peer open without any requests or responses. (default 30)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-concurrent-dials")
+                .long("max-concurrent-dials")
+                .value_name("DIALS")
+                .help("The maximum number of discovered peers dialed at once. Further peers are queued. (default 5)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-output")
+                .long("enr-output")
+                .value_name("PATH")
+                .help("Once the network has started, atomically write the base64 ENR of the node to this file, e.g., to collect bootnodes for a private testnet.")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("boot-nodes")
                 .long("boot-nodes")
+                .alias("boot-enr")
                 .allow_hyphen_values(true)
                 .value_name("BOOTNODES")
-                .help("One or more comma-delimited base64-encoded ENR's to bootstrap the p2p network.")
+                .help("One or more comma-delimited base64-encoded ENR's to bootstrap the p2p network. These are added to the discv5 routing table. May be repeated. Use --libp2p-addresses for plain multiaddrs.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("disable-discovery")
+                .long("disable-discovery")
+                .help("Disables the discv5 discovery protocol. The node will only connect to peers given by --libp2p-addresses and will not be discoverable by other nodes.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("disable-upnp")
+                .long("disable-upnp")
+                .help("Disables forwarding the libp2p TCP and discovery UDP ports with UPnP. Otherwise, if the listen address is not a loopback address and a router supports UPnP, the ports are forwarded while the node runs, and the external IP address of the router is published in the ENR if both are forwarded.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("moniker")
+                .long("moniker")
+                .value_name("NAME")
+                .help("A name for this node, appended to the agent string sent to peers, e.g., Lighthouse/v0.1.0-unstable/x86_64-linux/my-node-1. At most 32 printable ASCII characters, without spaces or '/'.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("bootnode")
+                .long("bootnode")
+                .help("Declares that this node is run as a stable bootnode. This is reported over the REST API and does not change the behaviour of the node.")
+                .takes_value(false)
+                .conflicts_with("disable-discovery"),
+        )
         .arg(
             Arg::with_name("discovery-port")
                 .long("disc-port")
@@ -87,7 +270,28 @@ fn main() {
             Arg::with_name("discovery-address")
                 .long("discovery-address")
                 .value_name("ADDRESS")
-                .help("The IP address to broadcast to other peers on how to reach this node.")
+                .help("The IP address the discovery UDP socket listens on, if different to the first --listen-address, e.g., another interface of a multi-homed host. Unless --enr-address is given, this is also the address broadcast to other peers on how to reach this node.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-address")
+                .long("enr-address")
+                .value_name("ADDRESS")
+                .help("The IP address to publish in the local ENR, overriding --discovery-address. Use this when behind a static NAT.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-tcp-port")
+                .long("enr-tcp-port")
+                .value_name("PORT")
+                .help("The TCP port to publish in the local ENR, overriding --port. Use this when the libp2p port is forwarded from a different external port.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-udp-port")
+                .long("enr-udp-port")
+                .value_name("PORT")
+                .help("The UDP port to publish in the local ENR, overriding --disc-port. Discovery also listens on this port.")
                 .takes_value(true),
         )
         .arg(
@@ -97,11 +301,45 @@ fn main() {
                 .help("One or more comma-delimited gossipsub topic strings to subscribe to.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("subscribe-all-subnets")
+                .long("subscribe-all-subnets")
+                .help("Subscribe to all attestation subnet topics. Intended for relays and block explorers. WARNING: this significantly increases bandwidth usage.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("gossip-d")
+                .long("gossip-d")
+                .value_name("PEERS")
+                .help("The target number of peers in each gossipsub topic mesh (D). Must be between --gossip-d-low and --gossip-d-high.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gossip-d-low")
+                .long("gossip-d-low")
+                .value_name("PEERS")
+                .help("Peers are added to a gossipsub topic mesh with fewer than this many peers (D_low).")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gossip-d-high")
+                .long("gossip-d-high")
+                .value_name("PEERS")
+                .help("Peers are removed from a gossipsub topic mesh with more than this many peers (D_high).")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gossip-heartbeat-interval")
+                .long("gossip-heartbeat-interval")
+                .value_name("MILLISECONDS")
+                .help("The time between gossipsub heartbeats, in milliseconds.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("libp2p-addresses")
                 .long("libp2p-addresses")
                 .value_name("MULTIADDR")
-                .help("One or more comma-delimited multiaddrs to manually connect to a libp2p peer without an ENR.")
+                .help("One or more comma-delimited multiaddrs to manually connect to a libp2p peer without an ENR. Multiaddrs in the LIGHTHOUSE_BOOT_NODES environment variable are also added.")
                 .takes_value(true),
         )
         /*
@@ -127,6 +365,22 @@ fn main() {
                 .help("Listen port for RPC endpoint.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("rpc-tls-cert")
+                .long("rpc-tls-cert")
+                .value_name("PEM_FILE")
+                .help("A PEM-encoded certificate for the RPC server. Requires --rpc-tls-key. When both are set the RPC uses TLS.")
+                .takes_value(true)
+                .requires("rpc-tls-key"),
+        )
+        .arg(
+            Arg::with_name("rpc-tls-key")
+                .long("rpc-tls-key")
+                .value_name("PEM_FILE")
+                .help("The PEM-encoded private key for --rpc-tls-cert.")
+                .takes_value(true)
+                .requires("rpc-tls-cert"),
+        )
         /* Client related arguments */
         .arg(
             Arg::with_name("api")
@@ -149,6 +403,28 @@ fn main() {
                 .help("Set the listen TCP port for the RESTful HTTP API server.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("rest-read-only")
+                .long("rest-read-only")
+                .help("Refuse all RESTful HTTP API requests other than GET, e.g., banning peers, on the --api-address listener. Useful when exposing the API publicly.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("rest-listener")
+                .long("rest-listener")
+                .value_name("ADDRESS:PORT:MODE")
+                .help("An additional listener for the RESTful HTTP API server, where MODE is full or read-only. May be given multiple times, e.g., for a read-only listener on a public interface.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("zero-ports")
+                .long("zero-ports")
+                .help("Use ports chosen by the OS for libp2p, discovery, RPC and the HTTP API. Useful when running many nodes on one host, e.g., in CI. The chosen ports are logged.")
+                .takes_value(false)
+                .conflicts_with_all(&["port", "discovery-port", "rpc-port", "api-port"]),
+        )
 
         /*
          * Database parameters.
@@ -157,10 +433,9 @@ fn main() {
             Arg::with_name("db")
                 .long("db")
                 .value_name("DB")
-                .help("Type of database to use.")
+                .help("Type of database to use. Defaults to disk for mainnet and memory for testnet specs.")
                 .takes_value(true)
-                .possible_values(&["disk", "memory"])
-                .default_value("memory"),
+                .possible_values(&["disk", "memory"]),
         )
         /*
          * Specification/testnet params.
@@ -168,11 +443,18 @@ fn main() {
         .arg(
             Arg::with_name("default-spec")
                 .long("default-spec")
-                .value_name("TITLE")
+                .value_name("TITLE_OR_PATH")
                 .short("default-spec")
-                .help("Specifies the default eth2 spec to be used. This will override any spec written to disk and will therefore be used by default in future instances.")
+                .help("Specifies the default eth2 spec to be used: one of mainnet, minimal or interop, or the path of an Eth2Config TOML or YAML file. This will override any spec written to disk and will therefore be used by default in future instances.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth2-config")
+                .long("eth2-config")
+                .value_name("PATH_OR_URL")
+                .help("A path or http(s):// URL of an Eth2Config TOML or YAML file. Like --default-spec, this will override any spec written to disk.")
                 .takes_value(true)
-                .possible_values(&["mainnet", "minimal", "interop"])
+                .conflicts_with("default-spec")
         )
         .arg(
             Arg::with_name("recent-genesis")
@@ -180,6 +462,34 @@ fn main() {
                 .short("r")
                 .help("When present, genesis will be within 30 minutes prior. Only for testing"),
         )
+        .arg(
+            Arg::with_name("genesis-time")
+                .long("genesis-time")
+                .value_name("UNIX_SECONDS")
+                .help("Generate a genesis state with the given genesis time, at most a year from now. Only for testing.")
+                .takes_value(true)
+                .conflicts_with_all(&["genesis-delay", "recent-genesis"])
+        )
+        .arg(
+            Arg::with_name("genesis-delay")
+                .long("genesis-delay")
+                .value_name("SECONDS")
+                .help("Generate a genesis state with a genesis time this many seconds from now (at most a year). Only for testing.")
+                .takes_value(true)
+                .conflicts_with_all(&["genesis-time", "recent-genesis"])
+        )
+        .arg(
+            Arg::with_name("deterministic-keys")
+                .long("deterministic-keys")
+                .help("Derive the validators of a generated genesis state from the interop key derivation, ignoring any keypairs file, and write their public keys to the datadir. Only for testing.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("wait-for-genesis")
+                .long("wait-for-genesis")
+                .help("If a generated genesis state is in the future, log a countdown and wait for genesis before starting the network and APIs. Only for testing.")
+                .takes_value(false)
+        )
         /*
          * Logging.
          */
@@ -187,11 +497,20 @@ fn main() {
             Arg::with_name("debug-level")
                 .long("debug-level")
                 .value_name("LEVEL")
-                .help("The title of the spec constants for chain config.")
+                .help("The level of log output, to the terminal or to --logfile. On Unix, a SIGHUP sets the level to that in the log_level file of the datadir, or back to this level if there is no such file.")
                 .takes_value(true)
                 .possible_values(&["info", "debug", "trace", "warn", "error", "crit"])
                 .default_value("trace"),
         )
+        .arg(
+            Arg::with_name("log-color")
+                .long("log-color")
+                .value_name("WHEN")
+                .help("Whether terminal log output is colored. With auto, output is colored only if it is written to a terminal.")
+                .takes_value(true)
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto"),
+        )
         .arg(
             Arg::with_name("verbosity")
                 .short("v")
@@ -199,6 +518,42 @@ fn main() {
                 .help("Sets the verbosity level")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .help("Only log warnings and errors. Takes precedence over --debug-level and -v.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("log-buffer-size")
+                .long("log-buffer-size")
+                .value_name("RECORDS")
+                .help("The number of log records buffered before records are dropped. Dropped records are reported in the log. Defaults to 1024.")
+                .takes_value(true)
+                .validator(|size| match size.parse::<usize>() {
+                    Ok(size) if size > 0 => Ok(()),
+                    _ => Err(format!("must be a positive integer, not {}", size)),
+                }),
+        )
+        .arg(
+            Arg::with_name("log-sync")
+                .long("log-sync")
+                .help("Write log records synchronously and in order instead of through a buffer. Intended for tests that assert on log output.")
+                .takes_value(false)
+                .conflicts_with("log-buffer-size")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("log-extra-field")
+                .long("log-extra-field")
+                .value_name("KEY=VALUE")
+                .help("A field added to every log record, e.g. to tell apart the logs of several nodes. May be given more than once.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .validator(|pair| logging::parse_extra_field(&pair).map(|_| ())),
+        )
         /*
          * Bootstrap.
          */
@@ -209,25 +564,133 @@ fn main() {
                 .help("Load the genesis state and libp2p address from the HTTP API of another Lighthouse node.")
                 .takes_value(true)
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("checkpoint-sync-url")
+                .long("checkpoint-sync-url")
+                .value_name("HTTP_SERVER")
+                .help("Start from the finalized checkpoint of the HTTP API of a trusted Lighthouse node. The checkpoint is fetched at startup to verify the server. Not yet supported when initializing the beacon chain.")
+                .takes_value(true)
+                .conflicts_with_all(&["bootstrap", "recent-genesis", "genesis-time", "genesis-delay"])
+        )
+        .arg(
+            Arg::with_name("graffiti")
+                .long("graffiti")
+                .value_name("GRAFFITI")
+                .help("The graffiti to include in produced blocks. Either text of at most 32 bytes, or exactly 32 bytes of 0x-prefixed hex.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fee-recipient")
+                .long("fee-recipient")
+                .value_name("ADDRESS")
+                .help("The 0x-prefixed, 20 byte address to which fees should be paid.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-skip-slots")
+                .long("max-skip-slots")
+                .value_name("SLOTS")
+                .help("The most consecutive skip slots the chain will process before pausing. Unlimited if not given.")
+                .takes_value(true),
+        )
+        /*
+         * External services.
+         */
+        .arg(
+            Arg::with_name("external-endpoint")
+                .long("external-endpoint")
+                .value_name("URL")
+                .help("A http(s) URL of an external endpoint the node may query for network-related services.")
+                .takes_value(true)
+        )
+        .subcommand(
+            SubCommand::with_name("inspect")
+                .about("Reports the configuration, database and network key in the datadir, then exits. Nothing is written and no services are started.")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the configuration and datadir summary as a single JSON document.")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("db")
+                .about("Maintenance of the database in the datadir.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("purge")
+                        .about("Deletes the chain database, keeping the configuration and network key, then exits. Unlike --testnet-reset, the configuration is left as it is.")
+                        .arg(
+                            Arg::with_name("yes")
+                                .long("yes")
+                                .help("Purge without asking for confirmation.")
+                                .takes_value(false),
+                        ),
+                ),
+        )
+        .get_matches_from(args);
 
     // build the initial logger
-    let decorator = slog_term::TermDecorator::new().build();
+    let decorator = match matches.value_of("log-color") {
+        Some("always") => slog_term::TermDecorator::new().force_color().build(),
+        Some("never") => slog_term::TermDecorator::new().force_plain().build(),
+        _ => slog_term::TermDecorator::new().build(),
+    };
     let decorator = logging::AlignedTermDecorator::new(decorator, logging::MAX_MESSAGE_WIDTH);
     let drain = slog_term::FullFormat::new(decorator).build().fuse();
-    let drain = slog_async::Async::new(drain).build();
-
-    let drain = match matches.value_of("debug-level") {
-        Some("info") => drain.filter_level(Level::Info),
-        Some("debug") => drain.filter_level(Level::Debug),
-        Some("trace") => drain.filter_level(Level::Trace),
-        Some("warn") => drain.filter_level(Level::Warning),
-        Some("error") => drain.filter_level(Level::Error),
-        Some("crit") => drain.filter_level(Level::Critical),
-        _ => unreachable!("guarded by clap"),
+
+    // `--quiet` overrides any other log level.
+    let quiet = matches.is_present("quiet");
+    let log_level_conflict =
+        quiet && (matches.occurrences_of("debug-level") > 0 || matches.is_present("verbosity"));
+
+    let cli_log_level = if quiet {
+        Level::Warning
+    } else {
+        matches
+            .value_of("debug-level")
+            .and_then(parse_log_level)
+            .expect("guarded by clap")
     };
 
-    let mut log = slog::Logger::root(drain.fuse(), o!());
+    // `--log-sync` writes each record before the logging call returns, so records are never
+    // dropped or reordered. Otherwise records go through a bounded async channel.
+    let (mut log, log_level) = if matches.is_present("log-sync") {
+        let drain = std::sync::Mutex::new(drain).fuse();
+        let (drain, log_level) = logging::ReloadableLevelFilter::new(drain, cli_log_level);
+        (slog::Logger::root(drain.fuse(), o!()), log_level)
+    } else {
+        let log_buffer_size = matches
+            .value_of("log-buffer-size")
+            .and_then(|size| size.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_LOG_BUFFER_SIZE);
+        let drain = slog_async::Async::new(drain)
+            .chan_size(log_buffer_size)
+            .overflow_strategy(OverflowStrategy::DropAndReport)
+            .build();
+        let (drain, log_level) = logging::ReloadableLevelFilter::new(drain, cli_log_level);
+        (slog::Logger::root(drain.fuse(), o!()), log_level)
+    };
+
+    let extra_fields = logging::ExtraFields::from_pairs(
+        matches.values_of("log-extra-field").into_iter().flatten(),
+    )
+    .expect("guarded by clap");
+    if !extra_fields.is_empty() {
+        log = log.new(slog::OwnedKV(extra_fields.clone()));
+    }
+
+    if log_level_conflict {
+        warn!(
+            log,
+            "Ignoring --debug-level and -v";
+            "reason" => "--quiet takes precedence"
+        );
+    }
+
+    for (flag, message) in deprecation_warnings {
+        warn!(log, "Deprecated flag"; "flag" => flag, "info" => message);
+    }
 
     warn!(
         log,
@@ -239,41 +702,249 @@ fn main() {
         .and_then(|v| Some(PathBuf::from(v)))
     {
         Some(v) => v,
-        None => {
-            // use the default
-            let mut default_dir = match dirs::home_dir() {
-                Some(v) => v,
-                None => {
-                    crit!(log, "Failed to find a home directory");
+        None => match default_data_dir() {
+            Ok(v) => v,
+            Err(e) => {
+                crit!(log, "Failed to find a default data dir"; "error" => e);
+                return;
+            }
+        },
+    };
+
+    // Both names have defaults, so they are always present.
+    let client_config_name = matches
+        .value_of("client-config-name")
+        .unwrap_or(CLIENT_CONFIG_FILENAME);
+    let eth2_config_name = matches
+        .value_of("eth2-config-name")
+        .unwrap_or(ETH2_CONFIG_FILENAME);
+    if client_config_name == eth2_config_name {
+        crit!(log, "The ClientConfig and Eth2Config must have different filenames"; "filename" => client_config_name);
+        return;
+    }
+
+    if let Some(inspect_matches) = matches.subcommand_matches("inspect") {
+        inspect::inspect_datadir(
+            &data_dir,
+            client_config_name,
+            eth2_config_name,
+            inspect_matches.is_present("json"),
+            &log,
+        );
+        return;
+    }
+
+    if let Some(purge_matches) = matches
+        .subcommand_matches("db")
+        .and_then(|db_matches| db_matches.subcommand_matches("purge"))
+    {
+        let db_path = match purge_db_path(&data_dir, client_config_name) {
+            Ok(Some(db_path)) => db_path,
+            Ok(None) => {
+                info!(log, "No database to purge"; "datadir" => format!("{:?}", data_dir));
+                return;
+            }
+            Err(e) => {
+                crit!(log, "Failed to find the database"; "error" => e);
+                return;
+            }
+        };
+        if !purge_matches.is_present("yes")
+            && !confirm(&format!("Delete the database at {:?}?", db_path))
+        {
+            info!(log, "Database purge cancelled");
+            return;
+        }
+        match purge_db(&db_path) {
+            Ok(freed) => info!(
+                log,
+                "Purged the database";
+                "path" => format!("{:?}", db_path),
+                "freed_mb" => freed / (1024 * 1024),
+            ),
+            Err(e) => crit!(log, "Failed to purge the database"; "error" => e),
+        }
+        return;
+    }
+
+    // A dry run validates the configuration without touching the filesystem.
+    let dry_run = matches.is_present("dry-run");
+
+    // create the directory if needed
+    if !dry_run {
+        if let Err(e) = fs::create_dir_all(&data_dir) {
+            crit!(
+                log,
+                "Failed to initialize data dir";
+                "error" => format!("{}", e),
+                "datadir" => format!("{:?}", data_dir),
+                "hint" => "use --datadir to choose a writable directory",
+            );
+            return;
+        }
+        if matches.value_of("data-dir-permissions") == Some("restricted") {
+            if let Err(e) = restrict_to_owner(&data_dir) {
+                crit!(log, "Failed to restrict data dir permissions"; "error" => format!("{}", e));
+                return;
+            }
+        }
+    }
+
+    // Initialise the `Eth2Config`.
+    //
+    // If a CLI parameter is set, overwrite any config file present.
+    // If a parameter is not set, use either the config file present or default to minimal.
+    let eth2_config_location = matches
+        .value_of("eth2-config")
+        .map(String::from)
+        .or_else(|| {
+            matches.value_of("testnet-dir").and_then(|dir| {
+                PathBuf::from(dir)
+                    .join(ETH2_CONFIG_FILENAME)
+                    .to_str()
+                    .map(String::from)
+            })
+        });
+    let cli_config = if let Some(location) = eth2_config_location {
+        match load_eth2_config(&location) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                crit!(log, "Failed to load the Eth2Config"; "error" => e);
+                return;
+            }
+        }
+    } else {
+        match matches.value_of("default-spec") {
+            Some(default_spec) => match Eth2Config::from_spec_constants(default_spec) {
+                Some(config) => Some(config),
+                // Anything other than a preset is the path of a custom `Eth2Config`.
+                None => match load_eth2_config(default_spec) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        crit!(log, "Failed to load the --default-spec Eth2Config"; "error" => e);
+                        return;
+                    }
+                },
+            },
+            // A network only supplies a spec if no other flag does.
+            None => match matches.value_of("network").map(Eth2Network::from_name) {
+                Some(Ok(network)) => Some(network.eth2_config()),
+                Some(Err(e)) => {
+                    crit!(log, "Failed to load the --network Eth2Config"; "error" => e);
                     return;
                 }
-            };
-            default_dir.push(DEFAULT_DATA_DIR);
-            default_dir
+                None => None,
+            },
         }
     };
 
-    // create the directory if needed
-    match fs::create_dir_all(&data_dir) {
-        Ok(_) => {}
-        Err(e) => {
-            crit!(log, "Failed to initialize data dir"; "error" => format!("{}", e));
+    // A bootstrap server on a different spec to that given on the CLI is on another network.
+    if let (Some(server), Some(cli_config)) = (matches.value_of("bootstrap"), &cli_config) {
+        if let Err(e) = check_bootstrap_spec(server, &cli_config.spec_constants) {
+            crit!(log, "Failed to verify the bootstrap server spec"; "error" => e);
+            return;
+        }
+    }
+
+    // `--testnet-reset` replaces the datadir configs and database with those of the spec given on
+    // the CLI, so it is handled before anything is read from the datadir.
+    if matches.is_present("testnet-reset") {
+        let spec_constants = match &cli_config {
+            Some(config) => &config.spec_constants,
+            None => {
+                crit!(log, "--testnet-reset requires a spec"; "reason" => "use --testnet-dir, --default-spec or --eth2-config");
+                return;
+            }
+        };
+        if spec_constants == "mainnet" {
+            crit!(log, "Refusing to reset a mainnet datadir"; "datadir" => format!("{:?}", data_dir));
+            return;
+        }
+        if !matches.is_present("yes")
+            && !confirm(&format!(
+                "Delete the database and configuration in {:?}?",
+                data_dir
+            ))
+        {
+            info!(log, "Testnet reset cancelled");
             return;
         }
+        let backup_dir = matches.value_of("backup-dir").map(PathBuf::from);
+        match reset_datadir(
+            &data_dir,
+            client_config_name,
+            eth2_config_name,
+            backup_dir.as_ref().map(PathBuf::as_path),
+        ) {
+            Ok((removed, backup)) => {
+                if let Some(backup) = backup {
+                    info!(log, "Backed up the datadir before testnet reset"; "path" => format!("{:?}", backup));
+                }
+                for path in removed {
+                    info!(log, "Removed for testnet reset"; "path" => format!("{:?}", path));
+                }
+            }
+            Err(e) => {
+                crit!(log, "Failed to reset the datadir"; "error" => e);
+                return;
+            }
+        }
     }
 
-    let client_config_path = data_dir.join(CLIENT_CONFIG_FILENAME);
+    let client_config_path = data_dir.join(client_config_name);
+
+    // Config files are verified against their checksums, unless the user has opted out.
+    let ignore_config_checksum = matches.is_present("ignore-config-checksum");
 
     // Attempt to load the `ClientConfig` from disk.
     //
     // If file doesn't exist, create a new, default one.
-    let mut client_config = match read_from_file::<ClientConfig>(client_config_path.clone()) {
-        Ok(Some(c)) => c,
+    let client_config_from_file = if ignore_config_checksum {
+        read_from_file::<ClientConfig>(client_config_path.clone())
+    } else {
+        read_from_file_verified::<ClientConfig>(client_config_path.clone())
+    };
+    let mut client_config = match client_config_from_file {
+        Ok(Some(c)) => {
+            // Keys from newer versions are ignored, but the user should know about them.
+            match unknown_keys(&client_config_path, &c) {
+                Ok(keys) => {
+                    for key in keys {
+                        warn!(log, "Ignoring unknown ClientConfig key"; "key" => key, "file" => format!("{:?}", client_config_path));
+                    }
+                }
+                Err(e) => warn!(log, "Unable to check ClientConfig for unknown keys"; "error" => e),
+            }
+            c
+        }
         Ok(None) => {
-            let default = ClientConfig::default();
-            if let Err(e) = write_to_file(client_config_path, &default) {
-                crit!(log, "Failed to write default ClientConfig to file"; "error" => format!("{:?}", e));
-                return;
+            // Prefer the client config supplied by a testnet definition, if any.
+            let default = match matches.value_of("testnet-dir") {
+                Some(dir) => match read_from_file::<ClientConfig>(
+                    PathBuf::from(dir).join(CLIENT_CONFIG_FILENAME),
+                ) {
+                    Ok(config) => config.unwrap_or_default(),
+                    Err(e) => {
+                        crit!(log, "Failed to load the testnet ClientConfig"; "error" => e);
+                        return;
+                    }
+                },
+                None => ClientConfig::default(),
+            };
+            // A spec given on the CLI determines the `spec_constants` of a new client config. An
+            // existing one keeps its own, which must then match the spec.
+            let default = match cli_config {
+                Some(ref cli_config) => ClientConfig {
+                    spec_constants: cli_config.spec_constants.clone(),
+                    ..default
+                },
+                None => default,
+            };
+            if !dry_run {
+                if let Err(e) = write_to_file(client_config_path, &default) {
+                    crit!(log, "Failed to write default ClientConfig to file"; "error" => format!("{:?}", e));
+                    return;
+                }
             }
             default
         }
@@ -295,21 +966,33 @@ fn main() {
         }
     };
 
-    let eth2_config_path = data_dir.join(ETH2_CONFIG_FILENAME);
+    // `--logfile` replaces the logger with a new root, so the level filter and extra fields are
+    // added again.
+    if matches.is_present("logfile") && !dry_run {
+        log = slog::Logger::root(log_level.filter(log).fuse(), o!());
+        if !extra_fields.is_empty() {
+            log = log.new(slog::OwnedKV(extra_fields));
+        }
+    }
 
-    // Initialise the `Eth2Config`.
-    //
-    // If a CLI parameter is set, overwrite any config file present.
-    // If a parameter is not set, use either the config file present or default to minimal.
-    let cli_config = match matches.value_of("default-spec") {
-        Some("mainnet") => Some(Eth2Config::mainnet()),
-        Some("minimal") => Some(Eth2Config::minimal()),
-        Some("interop") => Some(Eth2Config::interop()),
-        _ => None,
-    };
+    if !dry_run {
+        reload_log_level_on_sighup(
+            data_dir.join(LOG_LEVEL_FILENAME),
+            cli_log_level,
+            log_level,
+            log.clone(),
+        );
+    }
+
+    let eth2_config_path = data_dir.join(eth2_config_name);
     // if a CLI flag is specified, write the new config if it doesn't exist,
     // otherwise notify the user that the file will not be written.
-    let eth2_config_from_file = match read_from_file::<Eth2Config>(eth2_config_path.clone()) {
+    let eth2_config_from_file = if ignore_config_checksum {
+        read_from_file::<Eth2Config>(eth2_config_path.clone())
+    } else {
+        read_from_file_verified::<Eth2Config>(eth2_config_path.clone())
+    };
+    let eth2_config_from_file = match eth2_config_from_file {
         Ok(config) => config,
         Err(e) => {
             crit!(log, "Failed to read the Eth2Config from file"; "error" => format!("{:?}", e));
@@ -321,9 +1004,11 @@ fn main() {
         if let Some(cli_config) = cli_config {
             if eth2_config_from_file.is_none() {
                 // write to file if one doesn't exist
-                if let Err(e) = write_to_file(eth2_config_path, &cli_config) {
-                    crit!(log, "Failed to write default Eth2Config to file"; "error" => format!("{:?}", e));
-                    return;
+                if !dry_run {
+                    if let Err(e) = write_to_file(eth2_config_path, &cli_config) {
+                        crit!(log, "Failed to write default Eth2Config to file"; "error" => format!("{:?}", e));
+                        return;
+                    }
                 }
             } else {
                 warn!(
@@ -339,9 +1024,11 @@ fn main() {
                 None => {
                     // set default to minimal
                     let eth2_config = Eth2Config::minimal();
-                    if let Err(e) = write_to_file(eth2_config_path, &eth2_config) {
-                        crit!(log, "Failed to write default Eth2Config to file"; "error" => format!("{:?}", e));
-                        return;
+                    if !dry_run {
+                        if let Err(e) = write_to_file(eth2_config_path, &eth2_config) {
+                            crit!(log, "Failed to write default Eth2Config to file"; "error" => format!("{:?}", e));
+                            return;
+                        }
                     }
                     eth2_config
                 }
@@ -364,9 +1051,344 @@ fn main() {
         return;
     }
 
+    // The spec is now final, so the database default can be chosen.
+    client_config.apply_db_default(&matches);
+
+    let rpc_port = Some(client_config.rpc.port).filter(|_| client_config.rpc.enabled);
+    let rest_api_port =
+        Some(client_config.rest_api.port).filter(|_| client_config.rest_api.enabled);
+
+    // A single, stable record of the final configuration for log scrapers. Do not change the
+    // message or field names without good reason.
+    info!(
+        log,
+        "Configuration resolved";
+        "spec_constants" => &eth2_config.spec_constants,
+        "datadir" => format!("{:?}", client_config.data_dir),
+        "db_type" => &client_config.db_type,
+        "genesis_state" => format!("{:?}", client_config.genesis_state),
+        "libp2p_port" => client_config.network.libp2p_port,
+        "discovery_port" => client_config.network.discovery_port,
+        "rpc_port" => rpc_port,
+        "rest_api_port" => rest_api_port,
+        "max_peers" => client_config.network.max_peers,
+        "boot_nodes" => client_config.network.boot_nodes.len(),
+        "libp2p_nodes" => client_config.network.libp2p_nodes.len(),
+    );
+
+    if dry_run {
+        info!(log, "Dry run complete, exiting without writing any files");
+        return;
+    }
+
+    // Catch a read-only or full disk now, rather than as an obscure database error later.
+    let min_free_space_mb = matches
+        .value_of("min-free-space")
+        .and_then(|size| size.parse::<u64>().ok())
+        .expect("guarded by clap");
+    match client_config
+        .data_dir()
+        .ok_or_else(|| "Unable to open or create the datadir".to_string())
+        .and_then(|data_dir| disk_check::check_datadir(&data_dir, min_free_space_mb))
+    {
+        Ok(available) => info!(
+            log,
+            "Datadir is writable";
+            "free_space_mb" => available / (1024 * 1024),
+        ),
+        Err(e) => {
+            crit!(log, "Datadir check failed"; "error" => e);
+            return;
+        }
+    }
+
     // Start the node using a `tokio` executor.
     match run::run_beacon_node(client_config, eth2_config, &log) {
         Ok(_) => {}
         Err(e) => crit!(log, "Beacon node failed to start"; "reason" => format!("{:}", e)),
     }
 }
+
+/// Returns `DEFAULT_DATA_DIR` in the home directory.
+///
+/// Returns an error naming the resolved path if the home directory is unknown or does not exist,
+/// e.g., a `$HOME` that was never created in a minimal container image.
+fn default_data_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| {
+        "Unable to determine the home directory, use --datadir to choose a data dir".to_string()
+    })?;
+
+    if !home_dir.is_dir() {
+        return Err(format!(
+            "The home directory {:?} does not exist, so the default data dir {:?} cannot be \
+             created. Set $HOME to an existing directory or use --datadir",
+            home_dir,
+            home_dir.join(DEFAULT_DATA_DIR)
+        ));
+    }
+
+    Ok(home_dir.join(DEFAULT_DATA_DIR))
+}
+
+/// Returns an error if the `spec_constants` of the `Eth2Config` of the bootstrap `server` are not
+/// `expected`.
+fn check_bootstrap_spec(server: &str, expected: &str) -> Result<(), String> {
+    let server_config = Bootstrapper::from_server_string(server.to_string())?.eth2_config()?;
+
+    if server_config.spec_constants == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "Bootstrap server {} uses the {} spec, not {}",
+            server, server_config.spec_constants, expected
+        ))
+    }
+}
+
+/// Parses a log level as given to `--debug-level`.
+fn parse_log_level(level: &str) -> Option<Level> {
+    match level {
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        "warn" => Some(Level::Warning),
+        "error" => Some(Level::Error),
+        "crit" => Some(Level::Critical),
+        _ => None,
+    }
+}
+
+/// On each SIGHUP, sets the log level to that named in the file at `path`, or to
+/// `default_level` if there is no such file. Nothing other than the log level is reloaded.
+#[cfg(unix)]
+fn reload_log_level_on_sighup(
+    path: PathBuf,
+    default_level: Level,
+    log_level: logging::LevelHandle,
+    log: slog::Logger,
+) {
+    let signals = match signal_hook::iterator::Signals::new(&[signal_hook::SIGHUP]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            warn!(log, "Unable to listen for SIGHUP, the log level cannot be reloaded"; "error" => format!("{}", e));
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            let level = match fs::read_to_string(&path) {
+                Ok(contents) => match parse_log_level(contents.trim()) {
+                    Some(level) => level,
+                    None => {
+                        warn!(log, "Invalid log level, keeping the current level"; "file" => format!("{:?}", path), "level" => contents.trim());
+                        continue;
+                    }
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => default_level,
+                Err(e) => {
+                    warn!(log, "Unable to read the log level, keeping the current level"; "file" => format!("{:?}", path), "error" => format!("{}", e));
+                    continue;
+                }
+            };
+
+            log_level.set(level);
+            // Logged at the most severe level so that it is seen whatever the new level.
+            crit!(log, "Log level reloaded"; "level" => level.as_str());
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn reload_log_level_on_sighup(
+    _path: PathBuf,
+    _default_level: Level,
+    _log_level: logging::LevelHandle,
+    _log: slog::Logger,
+) {
+}
+
+/// Asks the user `question` on stdin, returning `true` only for "y" or "yes".
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => ["y", "yes"].contains(&answer.trim().to_lowercase().as_str()),
+        Err(_) => false,
+    }
+}
+
+/// Returns the path of the on-disk database of the datadir, or `None` if it has none.
+///
+/// The database location is read from the existing client config, if it can be.
+fn purge_db_path(data_dir: &Path, client_config_name: &str) -> Result<Option<PathBuf>, String> {
+    let mut client_config =
+        read_from_file::<ClientConfig>(data_dir.join(client_config_name))?.unwrap_or_default();
+    client_config.data_dir = data_dir.to_path_buf();
+
+    Ok(client_config.db_path_unchecked().filter(|p| p.exists()))
+}
+
+/// Deletes the database at `db_path`, returning the number of bytes freed.
+fn purge_db(db_path: &Path) -> Result<u64, String> {
+    let size = inspect::dir_size(db_path)?;
+    fs::remove_dir_all(db_path).map_err(|e| format!("Unable to remove {:?}: {}", db_path, e))?;
+
+    Ok(size)
+}
+
+/// Deletes the database and the named config files (and their checksums) from `data_dir`,
+/// returning the paths that were removed. Anything else in the datadir, such as the network key,
+/// is kept.
+///
+/// If `backup_dir` is given, the files are first copied into a new `backup_<timestamp>` directory
+/// within it, whose path is also returned.
+///
+/// The database location is read from the existing client config, if it can be.
+fn reset_datadir(
+    data_dir: &Path,
+    client_config_name: &str,
+    eth2_config_name: &str,
+    backup_dir: Option<&Path>,
+) -> Result<(Vec<PathBuf>, Option<PathBuf>), String> {
+    let mut client_config = read_from_file::<ClientConfig>(data_dir.join(client_config_name))
+        .ok()
+        .and_then(|config| config)
+        .unwrap_or_default();
+    client_config.data_dir = data_dir.to_path_buf();
+
+    let mut targets = vec![];
+    if let Some(db_path) = client_config.db_path_unchecked().filter(|p| p.exists()) {
+        targets.push(db_path);
+    }
+    for name in &[client_config_name, eth2_config_name] {
+        let config_path = data_dir.join(name);
+        for path in &[checksum_path(&config_path), config_path] {
+            if path.exists() {
+                targets.push(path.clone());
+            }
+        }
+    }
+
+    let backup = match backup_dir {
+        Some(backup_dir) => Some(backup_datadir(&targets, backup_dir)?),
+        None => None,
+    };
+
+    for path in &targets {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .map_err(|e| format!("Unable to remove {:?}: {}", path, e))?;
+    }
+
+    Ok((targets, backup))
+}
+
+/// Copies each of `paths` into a new `backup_<timestamp>` directory in `backup_dir`, returning
+/// the path of the new directory.
+///
+/// Returns an error before copying anything if `backup_dir` is not writable or its disk does not
+/// have room for `paths`.
+fn backup_datadir(paths: &[PathBuf], backup_dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(backup_dir)
+        .map_err(|e| format!("Unable to create backup dir {:?}: {}", backup_dir, e))?;
+    disk_check::check_writable(backup_dir)
+        .map_err(|e| format!("The backup dir {:?} is not writable: {}", backup_dir, e))?;
+
+    let mut required = 0;
+    for path in paths {
+        required += inspect::dir_size(path)?;
+    }
+    let available = disk_check::available_space(backup_dir)?;
+    if available < required {
+        return Err(format!(
+            "The backup dir {:?} has {} MB free, but the backup needs {} MB",
+            backup_dir,
+            available / disk_check::BYTES_PER_MB,
+            required / disk_check::BYTES_PER_MB + 1
+        ));
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Unable to read system time: {:?}", e))?
+        .as_secs();
+    let backup = backup_dir.join(format!("backup_{}", timestamp));
+    fs::create_dir(&backup).map_err(|e| format!("Unable to create {:?}: {}", backup, e))?;
+
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format!("Unable to back up {:?}: no file name", path))?;
+        copy_recursively(path, &backup.join(file_name))?;
+    }
+
+    Ok(backup)
+}
+
+/// Copies the file or directory `from` to `to`, including the contents of any subdirectories.
+fn copy_recursively(from: &Path, to: &Path) -> Result<(), String> {
+    if from.is_dir() {
+        fs::create_dir(to).map_err(|e| format!("Unable to create {:?}: {}", to, e))?;
+        for entry in fs::read_dir(from).map_err(|e| format!("Unable to read {:?}: {}", from, e))? {
+            let entry = entry.map_err(|e| format!("Unable to read {:?}: {}", from, e))?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| format!("Unable to copy {:?} to {:?}: {}", from, to, e))
+    }
+}
+
+/// Ensures a config filename names a file directly within the datadir.
+fn validate_file_name(name: String) -> Result<(), String> {
+    match Path::new(&name).file_name() {
+        Some(file_name) if file_name == name.as_str() => Ok(()),
+        _ => Err(format!("{} is not a filename", name)),
+    }
+}
+
+/// Loads an `Eth2Config` from `location`, which may either be a path on the local filesystem or a
+/// `http://`/`https://` URL.
+///
+/// Returns an error if the config cannot be read or uses `spec_constants` that are not supported
+/// by this binary.
+fn load_eth2_config(location: &str) -> Result<Eth2Config, String> {
+    let config = if location.starts_with("http://") || location.starts_with("https://") {
+        client::get_eth2_config(location)?
+    } else {
+        read_from_file::<Eth2Config>(PathBuf::from(location))?
+            .ok_or_else(|| format!("Eth2Config file does not exist: {}", location))?
+    };
+
+    if Eth2Config::from_spec_constants(&config.spec_constants).is_some() {
+        Ok(config)
+    } else {
+        Err(format!(
+            "Eth2Config from {} has unsupported spec_constants: {}",
+            location, config.spec_constants
+        ))
+    }
+}
+
+/// Makes the directory at `path` accessible only by its owner. A no-op on platforms other than
+/// Unix.
+#[cfg(unix)]
+fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn restrict_to_owner(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
@@ -4,7 +4,7 @@ use client::{
 };
 use futures::sync::oneshot;
 use futures::Future;
-use slog::{error, info};
+use slog::{error, info, warn};
 use std::cell::RefCell;
 use std::path::Path;
 use std::path::PathBuf;
@@ -52,6 +52,14 @@ pub fn run_beacon_node(
         "db_type" => &other_client_config.db_type,
     );
 
+    if db_type == "memory" {
+        warn!(
+            log,
+            "Using an in-memory database";
+            "msg" => "all chain data will be lost on shutdown, use --db disk to persist it"
+        );
+    }
+
     match (db_type.as_str(), spec_constants.as_str()) {
         ("disk", "minimal") => run::<ClientType<DiskStore, MinimalEthSpec>>(
             &db_path,
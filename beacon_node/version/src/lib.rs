@@ -23,3 +23,19 @@ pub fn version() -> String {
         platform()
     )
 }
+
+/// The semantic version of the beacon node, e.g., `0.1.0`.
+pub fn semantic_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// The abbreviated git commit hash the beacon node was built from, or `unknown` if it was not
+/// built from a git checkout.
+pub fn commit() -> &'static str {
+    env!("GIT_COMMIT_HASH")
+}
+
+/// The target triple the beacon node was built for, e.g., `x86_64-unknown-linux-gnu`.
+pub fn build_target() -> &'static str {
+    env!("BUILD_TARGET")
+}
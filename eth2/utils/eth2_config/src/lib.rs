@@ -1,9 +1,10 @@
 use clap::ArgMatches;
 use serde_derive::{Deserialize, Serialize};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use toml_edit::{Document, Item, Table};
 use types::ChainSpec;
 
 /// The core configuration of a Lighthouse beacon node.
@@ -44,6 +45,75 @@ impl Eth2Config {
             spec: ChainSpec::interop(),
         }
     }
+
+    /// Returns the preset named `spec_constants` (`mainnet`, `minimal` or `interop`), if any.
+    pub fn from_spec_constants(spec_constants: &str) -> Option<Self> {
+        match spec_constants {
+            "mainnet" => Some(Self::mainnet()),
+            "minimal" => Some(Self::minimal()),
+            "interop" => Some(Self::interop()),
+            _ => None,
+        }
+    }
+
+    /// Sets the minimum genesis time to the most recent 30 minute boundary.
+    pub fn set_recent_genesis(&mut self) {
+        self.spec.min_genesis_time = recent_genesis_time()
+    }
+}
+
+/// A well-known network, bundling its spec with its chain id and boot nodes, as selected by
+/// `--network`.
+#[derive(Debug, PartialEq)]
+pub struct Eth2Network {
+    pub name: &'static str,
+    /// The preset accepted by `Eth2Config::from_spec_constants`.
+    pub spec_constants: &'static str,
+    /// The chain id, i.e., `ChainSpec::network_id`.
+    pub network_id: u8,
+    /// The ENRs of the nodes to bootstrap discovery from.
+    pub boot_enrs: &'static [&'static str],
+}
+
+/// The networks accepted by `Eth2Network::from_name`.
+pub const NETWORKS: &[Eth2Network] = &[
+    Eth2Network {
+        name: "local",
+        spec_constants: "minimal",
+        network_id: 2,
+        boot_enrs: &[],
+    },
+    Eth2Network {
+        name: "interop",
+        spec_constants: "interop",
+        network_id: 13,
+        boot_enrs: &[],
+    },
+];
+
+impl Eth2Network {
+    /// Returns the network named `name` from `NETWORKS`.
+    pub fn from_name(name: &str) -> Result<&'static Self, String> {
+        NETWORKS
+            .iter()
+            .find(|network| network.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = NETWORKS.iter().map(|network| network.name).collect();
+                format!(
+                    "Unknown network: {}, expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Returns the `Eth2Config` of the network.
+    pub fn eth2_config(&self) -> Eth2Config {
+        let mut config = Eth2Config::from_spec_constants(self.spec_constants)
+            .expect("networks use a known preset");
+        config.spec.network_id = self.network_id;
+        config
+    }
 }
 
 impl Eth2Config {
@@ -53,7 +123,7 @@ impl Eth2Config {
     /// invalid.
     pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), &'static str> {
         if args.is_present("recent-genesis") {
-            self.spec.min_genesis_time = recent_genesis_time()
+            self.set_recent_genesis();
         }
 
         Ok(())
@@ -73,27 +143,124 @@ fn recent_genesis_time() -> u64 {
     now - secs_after_last_period
 }
 
-/// Write a configuration to file.
+/// Write a configuration to file, as YAML if `path` has a `.yaml` or `.yml` extension and as TOML
+/// otherwise.
+///
+/// The configuration and its checksum are each written with `write_atomically`, so neither is
+/// left partially written if the process is killed.
 pub fn write_to_file<T>(path: PathBuf, config: &T) -> Result<(), String>
 where
     T: Default + serde::de::DeserializeOwned + serde::Serialize,
 {
-    if let Ok(mut file) = File::create(path.clone()) {
-        let toml_encoded = toml::to_string(&config).map_err(|e| {
-            format!(
-                "Failed to write configuration to {:?}. Error: {:?}",
-                path, e
-            )
-        })?;
-        file.write_all(toml_encoded.as_bytes())
-            .unwrap_or_else(|_| panic!("Unable to write to {:?}", path));
+    let encoded = if is_yaml(&path) {
+        serde_yaml::to_string(&config).map_err(|e| format!("{:?}", e))
+    } else {
+        toml::to_string(&config).map_err(|e| format!("{:?}", e))
     }
+    .map_err(|e| format!("Failed to write configuration to {:?}. Error: {}", path, e))?;
+
+    write_atomically(&path, encoded.as_bytes())?;
+
+    // Write a checksum alongside the config so that corruption can be detected on load.
+    write_atomically(
+        &checksum_path(&path),
+        checksum(encoded.as_bytes()).as_bytes(),
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to write configuration checksum for {:?}. Error: {}",
+            path, e
+        )
+    })?;
 
     Ok(())
 }
 
+/// Writes `config` as TOML to `path`, editing any existing file in place so that its comments,
+/// ordering and formatting are kept.
+///
+/// Only the values which differ from `config` are replaced, keys which `config` no longer has are
+/// removed and new keys are appended. The file is written with `write_atomically`.
+pub fn update_toml_file<T: serde::Serialize>(path: &Path, config: &T) -> Result<(), String> {
+    let encoded = toml::to_string(config)
+        .map_err(|e| format!("Failed to encode configuration for {:?}: {}", path, e))?;
+    if !path.exists() {
+        return write_atomically(path, encoded.as_bytes());
+    }
+
+    let existing = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {:?}. Error: {:?}", path, e))?;
+    let mut document = existing
+        .parse::<Document>()
+        .map_err(|e| format!("Unable to parse {:?}: {}", path, e))?;
+    let updated = encoded
+        .parse::<Document>()
+        .map_err(|e| format!("Unable to parse the encoded configuration: {}", e))?;
+
+    update_table(document.as_table_mut(), updated.as_table());
+
+    write_atomically(path, document.to_string().as_bytes())
+}
+
+/// Makes `existing` hold the same keys and values as `updated`, keeping the decoration of every
+/// key and value of `existing` that is kept.
+fn update_table(existing: &mut Table, updated: &Table) {
+    let removed: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !updated.contains_key(key))
+        .collect();
+    for key in removed {
+        existing.remove(&key);
+    }
+
+    for (key, updated_item) in updated.iter() {
+        let existing_item = existing.entry(key);
+        match (existing_item, updated_item) {
+            (Item::Table(existing_table), Item::Table(updated_table)) => {
+                update_table(existing_table, updated_table)
+            }
+            (Item::Value(existing_value), Item::Value(updated_value)) => {
+                let decor = existing_value.decor();
+                let replacement =
+                    toml_edit::decorated(updated_value.clone(), decor.prefix(), decor.suffix());
+                if replacement.to_string() != existing_value.to_string() {
+                    *existing_value = replacement;
+                }
+            }
+            (existing_item, updated_item) => *existing_item = updated_item.clone(),
+        }
+    }
+}
+
+/// Replaces the contents of `path` with `contents`, such that `path` holds either its old or its
+/// new contents even if the process is killed part-way through.
+///
+/// The contents are written and synced to a temporary file beside `path`, which is then renamed
+/// over `path`.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)
+        .map_err(|e| format!("Unable to create {:?}. Error: {:?}", tmp_path, e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Unable to write {:?}. Error: {:?}", tmp_path, e))?;
+
+    fs::rename(&tmp_path, path).map_err(|e| {
+        format!(
+            "Unable to rename {:?} to {:?}. Error: {:?}",
+            tmp_path, path, e
+        )
+    })
+}
+
 /// Loads a `ClientConfig` from file. If unable to load from file, generates a default
 /// configuration and saves that as a sample file.
+///
+/// The file may be TOML or YAML, see `parse_config`.
 pub fn read_from_file<T>(path: PathBuf) -> Result<Option<T>, String>
 where
     T: Default + serde::de::DeserializeOwned + serde::Serialize,
@@ -103,11 +270,130 @@ where
         file.read_to_string(&mut contents)
             .map_err(|e| format!("Unable to read {:?}. Error: {:?}", path, e))?;
 
-        let config = toml::from_str(&contents)
-            .map_err(|e| format!("Unable to parse {:?}: {:?}", path, e))?;
+        // Unknown keys are ignored, see `unknown_keys`. Missing keys are an error which names the
+        // missing field.
+        let config = parse_config(&contents, &path)?;
 
         Ok(Some(config))
     } else {
         Ok(None)
     }
 }
+
+/// Parses a configuration read from `path`, choosing the format by the file extension.
+///
+/// `.toml` files are parsed as TOML and `.yaml` or `.yml` files as YAML. Anything else is tried as
+/// TOML and then as YAML, and both errors are reported if neither succeeds.
+pub fn parse_config<T>(contents: &str, path: &Path) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("toml") => {
+            toml::from_str(contents).map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+        }
+        _ if is_yaml(path) => {
+            serde_yaml::from_str(contents).map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+        }
+        _ => toml::from_str(contents).or_else(|toml_error| {
+            serde_yaml::from_str(contents).map_err(|yaml_error| {
+                format!(
+                    "Unable to parse {:?} as TOML ({}) or YAML ({})",
+                    path, toml_error, yaml_error
+                )
+            })
+        }),
+    }
+}
+
+/// Loads a configuration from file, as per `read_from_file`, after verifying the file against the
+/// `.sha256` checksum written by `write_to_file`.
+///
+/// Files without a checksum (e.g., those created by older versions) are loaded without
+/// verification.
+pub fn read_from_file_verified<T>(path: PathBuf) -> Result<Option<T>, String>
+where
+    T: Default + serde::de::DeserializeOwned + serde::Serialize,
+{
+    if let Ok(expected) = std::fs::read_to_string(checksum_path(&path)) {
+        let contents = std::fs::read(&path)
+            .map_err(|e| format!("Unable to read {:?}. Error: {:?}", path, e))?;
+
+        if checksum(&contents) != expected.trim() {
+            return Err(format!(
+                "Config file {:?} is corrupted or has been modified (checksum mismatch)",
+                path
+            ));
+        }
+    }
+
+    read_from_file(path)
+}
+
+/// Returns the dotted paths (e.g., `network.old_field`) of all keys in the file at `path`
+/// which are not present in `config`, the configuration that was loaded from it.
+///
+/// Such keys are silently ignored during deserialization, usually because the file was written
+/// by a newer version.
+pub fn unknown_keys<T: serde::Serialize>(path: &Path, config: &T) -> Result<Vec<String>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {:?}. Error: {:?}", path, e))?;
+    let file = parse_config::<toml::Value>(&contents, path)?;
+    let known = toml::Value::try_from(config)
+        .map_err(|e| format!("Unable to encode configuration: {}", e))?;
+
+    let mut unknown = vec![];
+    if let (toml::Value::Table(file), toml::Value::Table(known)) = (&file, &known) {
+        collect_unknown_keys(file, known, "", &mut unknown);
+    }
+
+    Ok(unknown)
+}
+
+/// Recursively adds the keys of `file` which are not in `known` to `unknown`.
+fn collect_unknown_keys(
+    file: &toml::value::Table,
+    known: &toml::value::Table,
+    prefix: &str,
+    unknown: &mut Vec<String>,
+) {
+    for (key, value) in file {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match (value, known.get(key)) {
+            (_, None) => unknown.push(path),
+            (toml::Value::Table(file), Some(toml::Value::Table(known))) => {
+                collect_unknown_keys(file, known, &path, unknown)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns `true` if `path` has a `.yaml` or `.yml` extension.
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml")
+        })
+}
+
+/// Returns the path of the checksum file for the configuration at `path`.
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.as_os_str().to_owned();
+    checksum_path.push(".sha256");
+    PathBuf::from(checksum_path)
+}
+
+/// Returns the hex-encoded SHA256 digest of `bytes`.
+fn checksum(bytes: &[u8]) -> String {
+    eth2_hashing::hash(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,51 @@
+use slog::{Record, Serializer, KV};
+
+/// Static `key=value` pairs attached to every record of a logger.
+///
+/// slog keys must be `&'static str`, so the keys are leaked. This is intended for the handful of
+/// fields given on the command line, which live as long as the process anyway.
+#[derive(Clone, Default)]
+pub struct ExtraFields(Vec<(&'static str, String)>);
+
+impl ExtraFields {
+    /// Parses each of `pairs` with `parse_extra_field`.
+    pub fn from_pairs<'a, I>(pairs: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        pairs
+            .into_iter()
+            .map(|pair| {
+                let (key, value) = parse_extra_field(pair)?;
+                let key: &'static str = Box::leak(key.into_boxed_str());
+                Ok((key, value))
+            })
+            .collect::<Result<_, String>>()
+            .map(ExtraFields)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl KV for ExtraFields {
+    fn serialize(&self, _record: &Record, serializer: &mut dyn Serializer) -> slog::Result {
+        for (key, value) in &self.0 {
+            serializer.emit_str(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `pair` at the first `=` into a non-empty key and a (possibly empty) value.
+pub fn parse_extra_field(pair: &str) -> Result<(String, String), String> {
+    let mut split = pair.splitn(2, '=');
+    match (split.next(), split.next()) {
+        (Some(key), Some(value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        (_, Some(_)) => Err(format!("the key of {} is empty", pair)),
+        _ => Err(format!("must be of the form KEY=VALUE, not {}", pair)),
+    }
+}
@@ -0,0 +1,63 @@
+use slog::{Drain, Level, OwnedKVList, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A drain which only passes on records at or above a level that can be changed while the drain
+/// is in use, via the `LevelHandle` returned by `new`.
+pub struct ReloadableLevelFilter<D> {
+    drain: D,
+    level: Arc<AtomicUsize>,
+}
+
+/// Changes the level of the `ReloadableLevelFilter` it was created with.
+#[derive(Clone)]
+pub struct LevelHandle {
+    level: Arc<AtomicUsize>,
+}
+
+impl<D> ReloadableLevelFilter<D> {
+    pub fn new(drain: D, level: Level) -> (Self, LevelHandle) {
+        let level = Arc::new(AtomicUsize::new(level.as_usize()));
+        let handle = LevelHandle {
+            level: level.clone(),
+        };
+        (Self { drain, level }, handle)
+    }
+}
+
+impl LevelHandle {
+    /// Returns a filter on `drain` whose level is shared with, and changed by, this handle.
+    pub fn filter<D>(&self, drain: D) -> ReloadableLevelFilter<D> {
+        ReloadableLevelFilter {
+            drain,
+            level: self.level.clone(),
+        }
+    }
+
+    /// Sets the least severe level which is logged.
+    pub fn set(&self, level: Level) {
+        self.level.store(level.as_usize(), Ordering::Relaxed);
+    }
+
+    /// Returns the least severe level which is logged.
+    pub fn get(&self) -> Level {
+        load_level(&self.level)
+    }
+}
+
+impl<D: Drain> Drain for ReloadableLevelFilter<D> {
+    type Ok = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if record.level().is_at_least(load_level(&self.level)) {
+            self.drain.log(record, values).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn load_level(level: &AtomicUsize) -> Level {
+    Level::from_usize(level.load(Ordering::Relaxed)).unwrap_or(Level::Trace)
+}
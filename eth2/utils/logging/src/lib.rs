@@ -1,5 +1,11 @@
 use std::io::{Result, Write};
 
+mod extra_fields;
+mod level_filter;
+
+pub use extra_fields::{parse_extra_field, ExtraFields};
+pub use level_filter::{LevelHandle, ReloadableLevelFilter};
+
 pub const MAX_MESSAGE_WIDTH: usize = 40;
 
 pub struct AlignedTermDecorator {